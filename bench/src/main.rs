@@ -1,8 +1,8 @@
 #![allow(dead_code, unused_imports, unused_variables)]
 
 mod random;
-mod backtracking;
 
+use topogen::backtracking::Traversals;
 use topogen::glp::deltas;
 use topogen::graph::Graph;
 use topogen::graph::simple::SimpleGraph;
@@ -14,7 +14,7 @@ use std::{time::Instant, sync::{Mutex, Arc}};
 
 fn count_backtracking(g: SimpleGraph) -> usize {
     let mut total = 0;
-    let mut t = backtracking::Traversals::new(g);
+    let mut t = Traversals::new(g);
     for _ in &mut t {
         total += 1;
     }
@@ -38,7 +38,7 @@ fn main() {
         let seed = rng.gen();
 
         pool.execute(move || {
-            let g = random_graph(Some(seed), None, None, false);
+            let g = random_graph(Some(seed), None, None);
 
             let n = g.size();
             let m = g.edge_count();
@@ -0,0 +1,165 @@
+use crate::graph::Graph;
+
+/// Enumerates topological sorts by backtracking over the choice of next node, letting
+/// a predicate cut off every sort that starts with a rejected prefix.
+///
+/// Slower than [`toposorts`](crate::nrpr::toposorts) per sort, but when the predicate
+/// rejects most of them it never visits the sorts it would reject. A graph with a
+/// cycle has no sorts.
+pub struct Traversals<G: Graph, P = fn(&[usize]) -> bool> {
+    graph: G,
+    prune: P,
+    order: Vec<usize>,
+    choices: Vec<usize>,
+    next: Vec<usize>,
+    deps: Vec<usize>,
+    started: bool,
+}
+
+impl<G: Graph> Traversals<G> {
+    pub fn new(graph: G) -> Self {
+        Self::with_pruning(graph, |_| true)
+    }
+}
+
+impl<G: Graph, P: FnMut(&[usize]) -> bool> Traversals<G, P> {
+    /// Only yields the sorts every nonempty prefix of which is accepted by `prune`.
+    pub fn with_pruning(graph: G, prune: P) -> Self {
+        let n = graph.size();
+
+        let order = Vec::with_capacity(n);
+        let choices = Vec::with_capacity(n);
+        let mut deps = vec![0; n];
+
+        for (_, w) in graph.edges() {
+            deps[w] += 1;
+        }
+
+        let mut next = vec![];
+
+        for (v, &d) in deps.iter().enumerate() {
+            if d == 0 {
+                next.push(v);
+            }
+        }
+
+        Traversals { graph, prune, order, choices, next, deps, started: false }
+    }
+
+    fn is_done(&self) -> bool {
+        self.order.len() == self.graph.size()
+    }
+
+    fn advance(&mut self) {
+        self.advance_to(self.next.len() - 1)
+    }
+
+    fn advance_to(&mut self, choice: usize) {
+        let v = self.next.swap_remove(choice);
+
+        self.order.push(v);
+        self.choices.push(choice);
+
+        for w in self.graph.successors(v) {
+            self.deps[w] -= 1;
+            if self.deps[w] == 0 {
+                self.next.push(w);
+            }
+        }
+    }
+
+    fn backtrack(&mut self) -> usize {
+        let v = self.order.pop().unwrap();
+        let choice = self.choices.pop().unwrap();
+
+        // The successors freed by `v` are the last ones pushed to `next`.
+        let mut freed = 0;
+        for w in self.graph.successors(v) {
+            if self.deps[w] == 0 {
+                freed += 1;
+            }
+            self.deps[w] += 1;
+        }
+        self.next.truncate(self.next.len() - freed);
+
+        // inverse of swap_remove
+        self.next.push(v);
+        let len = self.next.len();
+        self.next.swap(choice, len - 1);
+
+        choice
+    }
+
+    // Moves to the next prefix that is not an extension of the current one.
+    fn sibling(&mut self) -> bool {
+        while self.choices.last() == Some(&0) {
+            self.backtrack();
+        }
+        if self.order.is_empty() {
+            return false;
+        }
+        let choice = self.backtrack();
+        self.advance_to(choice - 1);
+        true
+    }
+}
+
+impl<G: Graph, P: FnMut(&[usize]) -> bool> Iterator for Traversals<G, P> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started && (self.order.is_empty() || !self.sibling()) {
+            return None;
+        }
+        self.started = true;
+
+        loop {
+            let rejected = !self.order.is_empty() && !(self.prune)(&self.order);
+            if !rejected && self.is_done() {
+                return Some(self.order.clone());
+            }
+            if rejected || self.next.is_empty() {
+                if !self.sibling() {
+                    return None;
+                }
+            } else {
+                self.advance();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::nrpr::toposorts;
+    use crate::testing::{check_toposorts, dags};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(1, 2);
+        g.add_edge(2, 1);
+        assert_eq!(Traversals::new(g).count(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_nrpr(g in dags(0..7, 0..10)) {
+            check_toposorts(&g, Traversals::new(g.clone()))?;
+        }
+
+        #[test]
+        fn test_pruning(g in dags(0..7, 0..10), v in 0..7usize, i in 0..7usize) {
+            // Sorts with `v` at position `i`, rejecting prefixes as soon as they miss it.
+            let at = |l: &[usize]| l.len() <= i || l[i] == v;
+            let mut ts: Vec<_> = Traversals::with_pruning(g.clone(), at).collect();
+            let mut expected: Vec<_> = toposorts(g).filter(|l| at(l)).collect();
+            ts.sort();
+            expected.sort();
+            prop_assert_eq!(ts, expected);
+        }
+    }
+}
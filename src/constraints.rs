@@ -0,0 +1,376 @@
+use crate::graph::Graph;
+use crate::nrpr::toposort;
+use crate::prefix::Subproblem;
+
+use bit_vec::BitVec;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::RangeInclusive;
+
+/// Restrictions on where nodes may appear in a topological sort.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    prefix: Vec<usize>,
+    suffix: Vec<usize>,
+    windows: Vec<(usize, RangeInclusive<usize>)>,
+}
+
+impl Constraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sorts must start with exactly these nodes in this order.
+    pub fn set_prefix(&mut self, prefix: &[usize]) {
+        self.prefix = prefix.to_vec();
+    }
+
+    /// Sorts must end with exactly these nodes in this order.
+    pub fn set_suffix(&mut self, suffix: &[usize]) {
+        self.suffix = suffix.to_vec();
+    }
+
+    /// Node `v` must be at one of `positions`. Repeated calls for the same node
+    /// intersect the allowed positions.
+    pub fn restrict_position(&mut self, v: usize, positions: RangeInclusive<usize>) {
+        self.windows.push((v, positions));
+    }
+}
+
+/// Enumerates the topological sorts of a graph that satisfy the constraints.
+///
+/// With only a prefix and suffix, the sorts of the nodes in between are enumerated
+/// with NRPR. Position windows are handled by backtracking, after tightening each
+/// window by those of the node's predecessors and successors, and cutting off a
+/// branch as soon as the remaining nodes can no longer be matched to the remaining
+/// positions within their windows.
+///
+/// Panics if the graph has a cycle.
+pub fn constrained_toposorts<G: Graph>(g: &G, c: &Constraints) -> impl Iterator<Item = Vec<usize>> {
+    let l = toposort(g).unwrap_or_else(|e| panic!("{e}"));
+    let n = l.len();
+    let (p, s) = (c.prefix.len(), c.suffix.len());
+
+    if p + s > n {
+        return Constrained::Empty;
+    }
+
+    let mut at = vec![None; n];
+    let suffix = c.suffix.iter().enumerate().map(|(i, v)| (n - s + i, v));
+    for (i, &v) in c.prefix.iter().enumerate().chain(suffix) {
+        if v >= n || at[v].is_some() {
+            return Constrained::Empty;
+        }
+        at[v] = Some(i);
+    }
+
+    if c.windows.is_empty() {
+        // As far as edges to and from the prefix and suffix are concerned, the nodes
+        // in between may as well all be at position `p`.
+        let rank = |v: usize| at[v].unwrap_or(p);
+        if g.edges().any(|(v, w)| rank(v) >= rank(w) && (at[v].is_some() || at[w].is_some())) {
+            return Constrained::Empty;
+        }
+        let sub = Subproblem::with_suffix(g, c.prefix.clone(), c.suffix.clone());
+        return Constrained::Free(Box::new(sub.toposorts()));
+    }
+
+    if c.windows.iter().any(|(v, _)| *v >= n) {
+        return Constrained::Empty;
+    }
+
+    let mut lo: Vec<_> = at.iter().map(|i| i.unwrap_or(0)).collect();
+    let mut hi: Vec<_> = at.iter().map(|i| i.unwrap_or(n - 1)).collect();
+    for (v, w) in &c.windows {
+        lo[*v] = lo[*v].max(*w.start());
+        hi[*v] = hi[*v].min(*w.end());
+    }
+
+    let mut succ = vec![vec![]; n];
+    let mut in_deg = vec![0; n];
+    for (v, w) in g.edges() {
+        succ[v].push(w);
+        in_deg[w] += 1;
+    }
+
+    // A node can come no earlier than right after its predecessors can, and no later
+    // than right before its successors must.
+    for &v in &l {
+        for &w in &succ[v] {
+            lo[w] = lo[w].max(lo[v] + 1);
+        }
+    }
+    for &v in l.iter().rev() {
+        for &w in &succ[v] {
+            match hi[w].checked_sub(1) {
+                Some(h) => hi[v] = hi[v].min(h),
+                None => return Constrained::Empty,
+            }
+        }
+    }
+    if (0..n).any(|v| lo[v] > hi[v]) {
+        return Constrained::Empty;
+    }
+
+    let w = Windowed::new(succ, in_deg, lo, hi);
+    if !w.feasible() {
+        return Constrained::Empty;
+    }
+    Constrained::Windowed(Box::new(w))
+}
+
+enum Constrained {
+    Empty,
+    Free(Box<dyn Iterator<Item = Vec<usize>>>),
+    Windowed(Box<Windowed>),
+}
+
+impl Iterator for Constrained {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Constrained::Empty => None,
+            Constrained::Free(it) => it.next(),
+            Constrained::Windowed(it) => it.next(),
+        }
+    }
+}
+
+// Depth-first search over prefixes, with one level on `stack` per position holding
+// the nodes that may go there and how many of them have been tried.
+struct Windowed {
+    succ: Vec<Vec<usize>>,
+    in_deg: Vec<usize>,
+    lo: Vec<usize>,
+    hi: Vec<usize>,
+    // all nodes in order of `lo`
+    by_lo: Vec<usize>,
+    placed: BitVec,
+    // The number of unplaced nodes that must go at each position at the latest.
+    due: Vec<usize>,
+    l: Vec<usize>,
+    stack: Vec<(Vec<usize>, usize)>,
+    empty: bool,
+}
+
+impl Windowed {
+    fn new(succ: Vec<Vec<usize>>, in_deg: Vec<usize>, lo: Vec<usize>, hi: Vec<usize>) -> Self {
+        let n = succ.len();
+        let mut due = vec![0; n];
+        for &h in &hi {
+            due[h] += 1;
+        }
+        let mut by_lo: Vec<_> = (0..n).collect();
+        by_lo.sort_by_key(|&x| lo[x]);
+        let placed = BitVec::from_elem(n, false);
+        let l = Vec::with_capacity(n);
+        let mut w = Windowed { succ, in_deg, lo, hi, by_lo, placed, due, l, stack: vec![], empty: n == 0 };
+        let c = w.candidates();
+        w.stack.push((c, 0));
+        w
+    }
+
+    fn candidates(&self) -> Vec<usize> {
+        let p = self.l.len();
+        let n = self.succ.len();
+        if p == n || self.due[p] > 1 {
+            return vec![];
+        }
+        let forced = self.due[p] == 1;
+        (0..n).filter(|&x| {
+            !self.placed[x] && self.in_deg[x] == 0 && self.lo[x] <= p && (!forced || self.hi[x] == p)
+        }).collect()
+    }
+
+    // Whether the unplaced nodes can still be matched to the free positions within
+    // their windows, ignoring edges. Fills each position in turn with the available
+    // node whose window ends first, which succeeds whenever any matching does.
+    fn feasible(&self) -> bool {
+        let n = self.succ.len();
+        let mut heap = BinaryHeap::new();
+        let mut next = self.by_lo.iter().filter(|&&x| !self.placed[x]).peekable();
+        for q in self.l.len()..n {
+            while let Some(&&x) = next.peek() {
+                if self.lo[x] > q {
+                    break;
+                }
+                heap.push(Reverse(self.hi[x]));
+                next.next();
+            }
+            match heap.pop() {
+                Some(Reverse(h)) if h >= q => {},
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn place(&mut self, x: usize) {
+        self.placed.set(x, true);
+        self.due[self.hi[x]] -= 1;
+        for &w in &self.succ[x] {
+            self.in_deg[w] -= 1;
+        }
+        self.l.push(x);
+    }
+
+    fn unplace(&mut self) {
+        let x = self.l.pop().unwrap();
+        self.placed.set(x, false);
+        self.due[self.hi[x]] += 1;
+        for &w in &self.succ[x] {
+            self.in_deg[w] += 1;
+        }
+    }
+}
+
+impl Iterator for Windowed {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.empty {
+            self.empty = false;
+            return Some(vec![]);
+        }
+        loop {
+            let (cs, i) = self.stack.last_mut()?;
+            if let Some(&x) = cs.get(*i) {
+                *i += 1;
+                self.place(x);
+                if self.l.len() == self.succ.len() {
+                    let l = self.l.clone();
+                    self.unplace();
+                    return Some(l);
+                }
+                if !self.feasible() {
+                    self.unplace();
+                    continue;
+                }
+                let c = self.candidates();
+                self.stack.push((c, 0));
+            } else {
+                self.stack.pop();
+                if !self.stack.is_empty() {
+                    self.unplace();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::nrpr::toposorts;
+    use crate::testing::dags;
+    use proptest::prelude::*;
+
+    fn satisfies(l: &[usize], c: &Constraints) -> bool {
+        let n = l.len();
+        l.starts_with(&c.prefix)
+            && l.ends_with(&c.suffix)
+            && c.windows.iter().all(|(v, w)| w.contains(&l.iter().position(|x| x == v).unwrap()))
+            && n >= c.prefix.len() + c.suffix.len()
+    }
+
+    fn check(g: &SimpleGraph, c: &Constraints) {
+        let mut got: Vec<_> = constrained_toposorts(g, c).collect();
+        let mut expected: Vec<_> = toposorts(g.clone()).filter(|l| satisfies(l, c)).collect();
+        got.sort();
+        expected.sort();
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn test_init_and_teardown() {
+        // 0 is init, 5 is teardown, and 1..5 depend on init.
+        let mut g = SimpleGraph::new(6);
+        for v in 1..5 {
+            g.add_edge(0, v);
+        }
+        g.add_edge(1, 2);
+
+        let mut c = Constraints::new();
+        c.set_prefix(&[0]);
+        c.restrict_position(5, 3..=5);
+        check(&g, &c);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 36);
+
+        c.set_suffix(&[5]);
+        check(&g, &c);
+    }
+
+    #[test]
+    fn test_impossible() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+
+        let mut c = Constraints::new();
+        c.set_prefix(&[1]);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 0);
+
+        let mut c = Constraints::new();
+        c.set_suffix(&[0]);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 0);
+
+        let mut c = Constraints::new();
+        c.restrict_position(1, 0..=0);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 0);
+
+        let mut c = Constraints::new();
+        c.set_prefix(&[0, 1, 2, 0]);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 0);
+    }
+
+    #[test]
+    fn test_infeasible_windows() {
+        // Two nodes pinned to the same position, which used to take factorial time.
+        let n = 30;
+        let g = SimpleGraph::new(n);
+        let mut c = Constraints::new();
+        c.restrict_position(0, n - 2..=n - 2);
+        c.restrict_position(1, n - 2..=n - 2);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 0);
+
+        // Feasible at the start, but not after putting 2 or 3 first.
+        let mut c = Constraints::new();
+        c.restrict_position(0, 0..=1);
+        c.restrict_position(1, 0..=1);
+        let g = SimpleGraph::new(4);
+        assert_eq!(constrained_toposorts(&g, &c).count(), 4);
+        check(&g, &c);
+    }
+
+    fn gen_constraints(n: usize) -> impl Strategy<Value = Constraints> {
+        let prefix = prop::collection::vec(0..n, 0..3);
+        let suffix = prop::collection::vec(0..n, 0..3);
+        let windows = prop::collection::vec((0..n, 0..n, 0..n), 0..3);
+        (prefix, suffix, windows).prop_map(|(mut prefix, mut suffix, windows)| {
+            prefix.dedup();
+            suffix.retain(|v| !prefix.contains(v));
+            suffix.dedup();
+            let mut c = Constraints::new();
+            c.set_prefix(&prefix);
+            c.set_suffix(&suffix);
+            for (v, a, b) in windows {
+                c.restrict_position(v, a.min(b)..=a.max(b));
+            }
+            c
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_filter(
+            (g, c) in dags(1..7, 0..8).prop_flat_map(|g| {
+                let n = g.size();
+                (Just(g), gen_constraints(n))
+            })
+        ) {
+            check(&g, &c);
+        }
+    }
+}
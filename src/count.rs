@@ -0,0 +1,185 @@
+use crate::graph::Graph;
+
+use bit_vec::BitVec;
+use num_bigint::BigUint;
+use num_traits::{CheckedAdd, One, Zero};
+
+use std::collections::HashMap;
+
+pub(crate) fn preds<G: Graph>(g: &G) -> Vec<Vec<usize>> {
+    let mut preds = vec![vec![]; g.size()];
+    for (v, w) in g.edges() {
+        preds[w].push(v);
+    }
+    preds
+}
+
+// The minimal elements of the complement of the downset `d`.
+pub(crate) fn frontier<'a>(preds: &'a [Vec<usize>], d: &'a BitVec) -> impl Iterator<Item = usize> + 'a {
+    (0..preds.len()).filter(move |&x| !d[x] && preds[x].iter().all(|&v| d[v]))
+}
+
+// Walks the lattice of downsets level by level, counting the ways to reach each
+// one from the empty downset. Gives up with `None` on overflow.
+fn count_with<T: Clone + Zero + One + CheckedAdd>(preds: &[Vec<usize>]) -> Option<T> {
+    let n = preds.len();
+
+    let mut level = HashMap::from([(BitVec::from_elem(n, false), T::one())]);
+
+    for _ in 0..n {
+        let mut next = HashMap::new();
+        for (d, c) in level {
+            for x in frontier(preds, &d) {
+                let mut d1 = d.clone();
+                d1.set(x, true);
+                let t = next.entry(d1).or_insert_with(T::zero);
+                *t = t.checked_add(&c)?;
+            }
+        }
+        level = next;
+    }
+
+    Some(level.into_values().next().unwrap_or_else(T::zero))
+}
+
+// All downsets of a graph along with the number of ways to complete each one to a
+// topological sort.
+pub(crate) struct Lattice {
+    preds: Vec<Vec<usize>>,
+    index: HashMap<BitVec, usize>,
+    counts: Vec<BigUint>,
+}
+
+impl Lattice {
+    pub(crate) fn new<G: Graph>(g: &G) -> Self {
+        let preds = preds(g);
+        let n = preds.len();
+
+        let empty = BitVec::from_elem(n, false);
+        let mut downsets = vec![empty.clone()];
+        let mut index = HashMap::from([(empty, 0)]);
+
+        let mut i = 0;
+        while i < downsets.len() {
+            for x in frontier(&preds, &downsets[i]).collect::<Vec<_>>() {
+                let mut d = downsets[i].clone();
+                d.set(x, true);
+                if !index.contains_key(&d) {
+                    index.insert(d.clone(), downsets.len());
+                    downsets.push(d);
+                }
+            }
+            i += 1;
+        }
+
+        // Downsets were discovered in order of size, so going backwards every
+        // extension of a downset is counted before the downset itself.
+        let mut counts = vec![BigUint::zero(); downsets.len()];
+        for (i, d) in downsets.iter().enumerate().rev() {
+            if d.all() {
+                counts[i] = BigUint::one();
+            } else {
+                let mut c = BigUint::zero();
+                let mut d1 = d.clone();
+                for x in frontier(&preds, d) {
+                    d1.set(x, true);
+                    c += &counts[index[&d1]];
+                    d1.set(x, false);
+                }
+                counts[i] = c;
+            }
+        }
+
+        Lattice { preds, index, counts }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.preds.len()
+    }
+
+    // The nodes that can come right after those in `d`, in increasing order, each
+    // with the number of topological sorts that continue with it.
+    pub(crate) fn choices(&self, d: &BitVec) -> Vec<(usize, &BigUint)> {
+        let mut d1 = d.clone();
+        frontier(&self.preds, d).map(|x| {
+            d1.set(x, true);
+            let c = self.count(&d1);
+            d1.set(x, false);
+            (x, c)
+        }).collect()
+    }
+
+    // The number of topological sorts that start with the nodes in `d`, in any order.
+    pub(crate) fn count(&self, d: &BitVec) -> &BigUint {
+        &self.counts[self.index[d]]
+    }
+}
+
+/// Counts the topological sorts of a graph without enumerating them.
+///
+/// Takes time and memory proportional to the number of downsets of the graph, which
+/// is polynomial in its size for a fixed width. A graph with a cycle has no sorts.
+pub fn count_toposorts<G: Graph>(g: &G) -> BigUint {
+    let preds = preds(g);
+    match count_with::<u128>(&preds) {
+        Some(c) => c.into(),
+        None => count_with::<BigUint>(&preds).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::nrpr::toposorts;
+    use crate::testing::dags;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_chain_and_antichain() {
+        let mut g = SimpleGraph::new(6);
+        assert_eq!(count_toposorts(&g), BigUint::from(720u32));
+
+        for v in 0..5 {
+            g.add_edge(v, v + 1);
+        }
+        assert_eq!(count_toposorts(&g), BigUint::one());
+
+        assert_eq!(count_toposorts(&SimpleGraph::new(0)), BigUint::one());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        g.add_edge(1, 0);
+        assert_eq!(count_toposorts(&g), BigUint::zero());
+    }
+
+    #[test]
+    fn test_overflow() {
+        // Two chains of 70 interleave in binomial(140, 70) > u128::MAX ways.
+        let l = 70;
+        let mut g = SimpleGraph::new(2 * l);
+        for v in 0..l - 1 {
+            g.add_edge(v, v + 1);
+            g.add_edge(l + v, l + v + 1);
+        }
+
+        let mut binomial = BigUint::one();
+        for i in 0..l {
+            binomial = binomial * (2 * l - i) / (i + 1);
+        }
+
+        assert!(binomial > BigUint::from(u128::MAX));
+        assert_eq!(count_toposorts(&g), binomial);
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_nrpr(g in dags(1..8, 0..12)) {
+            let count = count_toposorts(&g);
+            prop_assert_eq!(count, BigUint::from(toposorts(g).count()));
+        }
+    }
+}
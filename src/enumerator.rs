@@ -0,0 +1,11 @@
+/// A cursor over the topological sorts of a graph, implemented by each enumeration engine.
+///
+/// `current` is a valid sort from construction on. Each engine visits every sort
+/// exactly once, in its own order.
+pub trait Enumerator {
+    fn current(&self) -> &[usize];
+
+    /// Moves to the next topological sort. Returns `false` once they are exhausted,
+    /// leaving `current` at the last one.
+    fn advance(&mut self) -> bool;
+}
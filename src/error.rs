@@ -0,0 +1,35 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The graph is not acyclic. Holds the nodes of one cycle in edge order.
+    Cycle(Vec<usize>),
+    /// The checkpoint is malformed or was taken on a different graph.
+    InvalidCheckpoint(&'static str),
+    /// An edge refers to a node that is not in the graph.
+    NodeOutOfRange { node: usize, size: usize },
+    /// An edge from a node to itself.
+    SelfLoop(usize),
+    /// An edge that is already in the graph.
+    DuplicateEdge(usize, usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Cycle(c) => {
+                write!(f, "graph has a cycle: ")?;
+                for v in c {
+                    write!(f, "{v} -> ")?;
+                }
+                write!(f, "{}", c[0])
+            },
+            Error::InvalidCheckpoint(reason) => write!(f, "invalid checkpoint: {reason}"),
+            Error::NodeOutOfRange { node, size } => write!(f, "node {node} out of range for graph of size {size}"),
+            Error::SelfLoop(v) => write!(f, "self-loop on node {v}"),
+            Error::DuplicateEdge(v, w) => write!(f, "duplicate edge {v} -> {w}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
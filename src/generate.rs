@@ -0,0 +1,174 @@
+#![cfg(feature = "rand")]
+
+//! Random DAGs of several shapes. In all of them, `0..n` is a topological sort.
+
+use crate::graph::simple::SimpleGraph;
+
+use rand::Rng;
+
+/// Adds each edge `v -> w` with `v < w` independently with probability `p`.
+pub fn erdos_renyi<R: Rng + ?Sized>(rng: &mut R, n: usize, p: f64) -> SimpleGraph {
+    let mut g = SimpleGraph::new(n);
+    for v in 0..n {
+        for w in v + 1..n {
+            if rng.gen_bool(p) {
+                g.add_edge(v, w);
+            }
+        }
+    }
+    g
+}
+
+/// Nodes split into consecutive layers of the given sizes, with each edge from one
+/// layer to the next present independently with probability `p`.
+pub fn layered<R: Rng + ?Sized>(rng: &mut R, layers: &[usize], p: f64) -> SimpleGraph {
+    let mut g = SimpleGraph::new(layers.iter().sum());
+    let mut start = 0;
+    for w in layers.windows(2) {
+        let next = start + w[0];
+        for v in start..next {
+            for u in next..next + w[1] {
+                if rng.gen_bool(p) {
+                    g.add_edge(v, u);
+                }
+            }
+        }
+        start = next;
+    }
+    g
+}
+
+/// A random recursive forest with `roots` trees, in which every other node hangs off
+/// a uniformly chosen earlier node.
+pub fn forest<R: Rng + ?Sized>(rng: &mut R, n: usize, roots: usize) -> SimpleGraph {
+    assert!(roots > 0 || n == 0, "a nonempty forest needs a root");
+    let mut g = SimpleGraph::new(n);
+    for v in roots.min(n)..n {
+        g.add_edge(rng.gen_range(0..v), v);
+    }
+    g
+}
+
+/// A random recursive tree rooted at node 0.
+pub fn tree<R: Rng + ?Sized>(rng: &mut R, n: usize) -> SimpleGraph {
+    forest(rng, n, 1)
+}
+
+/// A random series-parallel DAG, built from single nodes by repeatedly joining two
+/// neighbouring parts, each time with equal chance either in parallel or in series
+/// with every sink of the first before every source of the second.
+pub fn series_parallel<R: Rng + ?Sized>(rng: &mut R, n: usize) -> SimpleGraph {
+    let mut g = SimpleGraph::new(n);
+
+    // The sources and sinks of each part, with parts covering consecutive nodes.
+    let mut parts: Vec<(Vec<usize>, Vec<usize>)> = (0..n).map(|v| (vec![v], vec![v])).collect();
+
+    while parts.len() > 1 {
+        let i = rng.gen_range(0..parts.len() - 1);
+        let (sources, sinks) = parts.remove(i + 1);
+        let part = &mut parts[i];
+        if rng.gen() {
+            part.0.extend(sources);
+            part.1.extend(sinks);
+        } else {
+            for &v in &part.1 {
+                for &w in &sources {
+                    g.add_edge(v, w);
+                }
+            }
+            part.1 = sinks;
+        }
+    }
+
+    g
+}
+
+/// The product of a chain of `w` nodes and a chain of `h` nodes. Node `(x, y)` is
+/// `x * h + y`, with edges to `(x + 1, y)` and `(x, y + 1)`.
+pub fn grid(w: usize, h: usize) -> SimpleGraph {
+    let mut g = SimpleGraph::new(w * h);
+    for x in 0..w {
+        for y in 0..h {
+            let v = x * h + y;
+            if x + 1 < w {
+                g.add_edge(v, v + h);
+            }
+            if y + 1 < h {
+                g.add_edge(v, v + 1);
+            }
+        }
+    }
+    g
+}
+
+/// The interval order of `n` random intervals, with `v -> w` whenever interval `v`
+/// ends before interval `w` starts. Nodes are numbered by the start of their interval.
+pub fn interval_order<R: Rng + ?Sized>(rng: &mut R, n: usize) -> SimpleGraph {
+    let mut intervals: Vec<(f64, f64)> = (0..n).map(|_| {
+        let (a, b) = (rng.gen::<f64>(), rng.gen::<f64>());
+        (a.min(b), a.max(b))
+    }).collect();
+    intervals.sort_by(|x, y| x.partial_cmp(y).unwrap());
+
+    let mut g = SimpleGraph::new(n);
+    for (v, &(_, end)) in intervals.iter().enumerate() {
+        for (w, &(start, _)) in intervals.iter().enumerate().skip(v + 1) {
+            if end < start {
+                g.add_edge(v, w);
+            }
+        }
+    }
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::count_toposorts;
+    use crate::graph::Graph;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn assert_sorted(g: &SimpleGraph) {
+        assert!(g.edges().all(|(v, w)| v < w));
+    }
+
+    #[test]
+    fn test_families() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for n in [0, 1, 2, 10, 30] {
+            let gs = [
+                erdos_renyi(&mut rng, n, 0.3),
+                layered(&mut rng, &[n / 3, n / 3, n - 2 * (n / 3)], 0.5),
+                tree(&mut rng, n),
+                forest(&mut rng, n, 3),
+                series_parallel(&mut rng, n),
+                interval_order(&mut rng, n),
+            ];
+            for g in gs {
+                assert_eq!(g.size(), n);
+                assert_sorted(&g);
+            }
+        }
+
+        assert_eq!(tree(&mut rng, 20).edge_count(), 19);
+        assert_eq!(forest(&mut rng, 20, 4).edge_count(), 16);
+    }
+
+    #[test]
+    fn test_seeded() {
+        let g1 = erdos_renyi(&mut StdRng::seed_from_u64(3), 20, 0.5);
+        let g2 = erdos_renyi(&mut StdRng::seed_from_u64(3), 20, 0.5);
+        assert_eq!(g1.edges().collect::<Vec<_>>(), g2.edges().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_grid() {
+        // Sorts of a 2 by n grid are counted by the Catalan numbers.
+        let g = grid(2, 5);
+        assert_sorted(&g);
+        assert_eq!(g.edge_count(), 13);
+        assert_eq!(count_toposorts(&g), BigUint::from(42u32));
+    }
+}
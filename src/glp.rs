@@ -6,17 +6,26 @@ pub trait GLPSubProc {
     fn execute(&mut self, i: usize) -> (bool, Self::Delta);
 }
 
-struct GLPIterator<SP: GLPSubProc> {
-    proc: SP,
+pub(crate) struct GLPIterator<SP: GLPSubProc> {
+    pub(crate) proc: SP,
     p: Vec<usize>,
 }
 
 impl<SP: GLPSubProc> GLPIterator<SP> {
-    fn new(proc: SP) -> Self {
+    pub(crate) fn new(proc: SP) -> Self {
         let n = proc.size();
         let p = vec![0; n];
         GLPIterator { proc, p }
     }
+
+    pub(crate) fn resume(proc: SP, p: Vec<usize>) -> Self {
+        assert_eq!(p.len(), proc.size());
+        GLPIterator { proc, p }
+    }
+
+    pub(crate) fn focus(&self) -> &[usize] {
+        &self.p
+    }
 }
 
 impl<SP: GLPSubProc> Iterator for GLPIterator<SP> {
@@ -24,7 +33,7 @@ impl<SP: GLPSubProc> Iterator for GLPIterator<SP> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let n = self.p.len();
-        let i = self.p.get(0).copied().filter(|&i| i < n)?;
+        let i = self.p.first().copied().filter(|&i| i < n)?;
         let (vi, d) = self.proc.execute(i);
         if vi {
             self.p[i] = 0;
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::Graph;
+use super::simple::{self, SimpleGraph};
+use crate::nrpr::{self, Observer};
+
+/// A graph whose nodes are identified by keys of any type, numbered in order of
+/// first appearance.
+#[derive(Debug, Clone)]
+pub struct LabeledGraph<K> {
+    graph: SimpleGraph,
+    keys: Vec<K>,
+    index: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone> LabeledGraph<K> {
+    pub fn new() -> Self {
+        let graph = SimpleGraph::new(0);
+        LabeledGraph { graph, keys: vec![], index: HashMap::new() }
+    }
+
+    /// Returns the index of the node, adding it if it is new.
+    pub fn add_node(&mut self, k: K) -> usize {
+        if let Some(&v) = self.index.get(&k) {
+            return v;
+        }
+        let v = self.graph.add_node();
+        self.keys.push(k.clone());
+        self.index.insert(k, v);
+        v
+    }
+
+    pub fn add_edge(&mut self, from: K, to: K) {
+        let v = self.add_node(from);
+        let w = self.add_node(to);
+        self.graph.add_edge(v, w);
+    }
+
+    pub fn index(&self, k: &K) -> Option<usize> {
+        self.index.get(k).copied()
+    }
+
+    pub fn key(&self, v: usize) -> &K {
+        &self.keys[v]
+    }
+
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Panics if the graph has a cycle.
+    pub fn enumerator(&self) -> Enumerator<K> {
+        let inner = nrpr::Enumerator::new(self.graph.clone());
+        let labels = inner.current().iter().map(|&v| self.keys[v].clone()).collect();
+        Enumerator { inner, labels }
+    }
+
+    /// Like [`nrpr::toposorts`], with keys in place of node indices.
+    pub fn toposorts(&self) -> impl Iterator<Item = Vec<K>> + '_ {
+        nrpr::toposorts(self.graph.clone()).map(|l| l.into_iter().map(|v| self.keys[v].clone()).collect())
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for LabeledGraph<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> Graph for LabeledGraph<K> {
+    type Edges<'a> = simple::Edges<'a> where K: 'a;
+    type Successors<'a> = <SimpleGraph as Graph>::Successors<'a> where K: 'a;
+
+    fn size(&self) -> usize {
+        self.graph.size()
+    }
+
+    fn edges(&self) -> Self::Edges<'_> {
+        self.graph.edges()
+    }
+
+    fn successors(&self, v: usize) -> Self::Successors<'_> {
+        self.graph.successors(v)
+    }
+}
+
+/// Cursor over the topological sorts of a [`LabeledGraph`] as keys, in the same order
+/// as [`nrpr::Enumerator`]. Keys are moved around in place rather than cloned.
+pub struct Enumerator<K> {
+    inner: nrpr::Enumerator,
+    labels: Vec<K>,
+}
+
+struct Relabel<'a, K>(&'a mut [K]);
+
+impl<K> Observer for Relabel<'_, K> {
+    fn on_adjacent_swap(&mut self, pos: usize, _left: usize, _right: usize) {
+        self.0.swap(pos, pos + 1);
+    }
+}
+
+impl<K> Enumerator<K> {
+    pub fn current(&self) -> &[K] {
+        &self.labels
+    }
+
+    /// The node indices of `current`.
+    pub fn indices(&self) -> &[usize] {
+        self.inner.current()
+    }
+
+    pub fn advance(&mut self) -> bool {
+        self.inner.advance_with(&mut Relabel(&mut self.labels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks() -> LabeledGraph<&'static str> {
+        let mut g = LabeledGraph::new();
+        g.add_edge("init", "build");
+        g.add_edge("init", "fetch");
+        g.add_edge("build", "test");
+        g.add_edge("fetch", "test");
+        g.add_node("lint");
+        g
+    }
+
+    #[test]
+    fn test_interning() {
+        let g = tasks();
+        assert_eq!(g.size(), 5);
+        assert_eq!(g.edge_count(), 4);
+        assert_eq!(g.keys(), &["init", "build", "fetch", "test", "lint"]);
+        assert_eq!(g.index(&"test"), Some(3));
+        assert_eq!(g.index(&"deploy"), None);
+        assert_eq!(*g.key(2), "fetch");
+    }
+
+    #[test]
+    fn test_toposorts() {
+        let g = tasks();
+        let ts: Vec<_> = g.toposorts().collect();
+        assert_eq!(ts.len(), 10);
+
+        let mut en = g.enumerator();
+        for l in ts {
+            assert_eq!(en.current(), l);
+            let keys: Vec<_> = en.indices().iter().map(|&v| *g.key(v)).collect();
+            assert_eq!(keys, l);
+            en.advance();
+        }
+        assert!(!en.advance());
+    }
+}
@@ -1,6 +1,7 @@
+pub mod labeled;
 pub mod simple;
 
-mod petgraph;
+pub mod petgraph;
 mod graph_builder;
 
 pub trait Graph {
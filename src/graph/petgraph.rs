@@ -1,8 +1,11 @@
 #![cfg(feature = "petgraph")]
 
 use super::Graph;
+use super::simple::SimpleGraph;
+use crate::nrpr;
 use petgraph::Direction;
-use petgraph::visit::{NodeCount, NodeIndexable, IntoEdgeReferences, IntoNeighborsDirected, EdgeRef};
+use petgraph::visit::{GraphBase, NodeCount, NodeIndexable, IntoEdgeReferences, IntoNeighborsDirected, IntoNodeIdentifiers, EdgeRef};
+use std::ops::Index;
 
 pub struct Edges<'a, G: NodeIndexable + IntoEdgeReferences> {
     graph: &'a G,
@@ -34,12 +37,15 @@ impl<'a, G: NodeIndexable + IntoNeighborsDirected> Iterator for Successors<'a, G
     }
 }
 
+/// Nodes are numbered by [`NodeIndexable::to_index`], up to [`NodeIndexable::node_bound`].
+/// Vacant indices, as left by removing nodes from a `StableGraph`, are isolated nodes;
+/// go through [`Compact`] or the functions in this module to skip them.
 impl<G: NodeCount + NodeIndexable + IntoEdgeReferences + IntoNeighborsDirected> Graph for G {
     type Edges<'a> = Edges<'a, G> where G: 'a;
     type Successors<'a> = Successors<'a, G> where G: 'a;
 
     fn size(&self) -> usize {
-        self.node_count()
+        self.node_bound()
     }
 
     fn edges(&self) -> Self::Edges<'_> {
@@ -52,10 +58,86 @@ impl<G: NodeCount + NodeIndexable + IntoEdgeReferences + IntoNeighborsDirected>
     }
 }
 
+/// Any petgraph graph with its nodes renumbered `0..node_count()` in the order of
+/// `node_identifiers()`, so that vacant indices never show up in a sort.
+pub struct Compact<N> {
+    graph: SimpleGraph,
+    nodes: Vec<N>,
+}
+
+impl<N: Copy> Compact<N> {
+    pub fn new<G>(g: G) -> Self
+    where
+        G: NodeIndexable + IntoEdgeReferences + IntoNodeIdentifiers + GraphBase<NodeId = N>,
+    {
+        let nodes: Vec<_> = g.node_identifiers().collect();
+        let mut index = vec![0; g.node_bound()];
+        for (i, &v) in nodes.iter().enumerate() {
+            index[g.to_index(v)] = i;
+        }
+
+        let mut graph = SimpleGraph::new(nodes.len());
+        for e in g.edge_references() {
+            graph.add_edge(index[g.to_index(e.source())], index[g.to_index(e.target())]);
+        }
+
+        Compact { graph, nodes }
+    }
+
+    /// The node id of node `v`.
+    pub fn node(&self, v: usize) -> N {
+        self.nodes[v]
+    }
+
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+}
+
+impl<N> Graph for Compact<N> {
+    type Edges<'a> = <SimpleGraph as Graph>::Edges<'a> where N: 'a;
+    type Successors<'a> = <SimpleGraph as Graph>::Successors<'a> where N: 'a;
+
+    fn size(&self) -> usize {
+        self.graph.size()
+    }
+
+    fn edges(&self) -> Self::Edges<'_> {
+        self.graph.edges()
+    }
+
+    fn successors(&self, v: usize) -> Self::Successors<'_> {
+        self.graph.successors(v)
+    }
+}
+
+/// Like [`nrpr::toposorts`], with petgraph node ids in place of node indices. Vacant
+/// indices are skipped.
+pub fn toposorts<G>(g: G) -> impl Iterator<Item = Vec<G::NodeId>>
+where
+    G: NodeIndexable + IntoEdgeReferences + IntoNodeIdentifiers,
+{
+    let c = Compact::new(g);
+    let nodes = c.nodes.clone();
+    nrpr::toposorts(c).map(move |l| l.into_iter().map(|v| nodes[v]).collect())
+}
+
+/// Like [`nrpr::toposorts`], with references to node weights in place of node indices.
+pub fn toposorts_weights<'a, G, N: 'a>(g: &'a G) -> impl Iterator<Item = Vec<&'a N>>
+where
+    G: GraphBase + Index<G::NodeId, Output = N>,
+    &'a G: NodeIndexable + IntoEdgeReferences + IntoNodeIdentifiers + GraphBase<NodeId = G::NodeId>,
+{
+    toposorts(g).map(move |l| l.into_iter().map(|v| &g[v]).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use petgraph::prelude::*;
+    use super::Compact;
+    use crate::count::count_toposorts;
     use crate::graph::Graph;
+    use crate::nrpr::toposorts;
 
     #[test]
     fn test_size() {
@@ -88,6 +170,84 @@ mod tests {
         assert_eq!(edges, vec![(0, 1), (1, 2), (2, 3)]);
     }
 
+    #[test]
+    fn test_toposorts() {
+        let mut g = DiGraph::<&str, ()>::new();
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        let c = g.add_node("c");
+        g.add_edge(a, b, ());
+
+        let mut ts: Vec<_> = super::toposorts(&g).collect();
+        ts.sort();
+        assert_eq!(ts, vec![vec![a, b, c], vec![a, c, b], vec![c, a, b]]);
+
+        let mut ws: Vec<_> = super::toposorts_weights(&g).collect();
+        ws.sort();
+        assert_eq!(ws, vec![vec![&"a", &"b", &"c"], vec![&"a", &"c", &"b"], vec![&"c", &"a", &"b"]]);
+    }
+
+    #[test]
+    fn test_stable_graph() {
+        let mut g = StableDiGraph::<u32, ()>::new();
+        let ns: Vec<_> = (0..5).map(|i| g.add_node(i)).collect();
+        g.add_edge(ns[0], ns[4], ());
+        g.add_edge(ns[3], ns[4], ());
+        g.add_edge(ns[1], ns[2], ());
+        g.remove_node(ns[1]);
+        g.remove_node(ns[2]);
+
+        let c = Compact::new(&g);
+        assert_eq!(c.size(), 3);
+        assert_eq!(c.nodes(), [ns[0], ns[3], ns[4]]);
+        assert_eq!(c.edges().collect::<Vec<_>>(), vec![(0, 2), (1, 2)]);
+
+        let mut ls: Vec<_> = toposorts(Compact::new(&g)).collect();
+        ls.sort();
+        assert_eq!(ls, vec![vec![0, 1, 2], vec![1, 0, 2]]);
+        assert_eq!(count_toposorts(&Compact::new(&g)), 2u32.into());
+
+        let mut ts: Vec<_> = super::toposorts(&g).collect();
+        ts.sort();
+        assert_eq!(ts, vec![vec![ns[0], ns[3], ns[4]], vec![ns[3], ns[0], ns[4]]]);
+
+        let ws: Vec<_> = super::toposorts_weights(&g).collect();
+        assert_eq!(ws.len(), 2);
+        assert!(ws.iter().all(|l| l[2] == &4));
+    }
+
+    #[test]
+    fn test_stable_graph_vacant() {
+        let mut g = StableDiGraph::<u32, ()>::new();
+        let ns: Vec<_> = (0..5).map(|i| g.add_node(i)).collect();
+        g.add_edge(ns[0], ns[4], ());
+        g.add_edge(ns[3], ns[4], ());
+        g.remove_node(ns[1]);
+        g.remove_node(ns[2]);
+
+        assert_eq!(Graph::size(&&g), 5);
+        assert_eq!(count_toposorts(&&g), 40u32.into());
+
+        let ls: Vec<_> = toposorts(&g).collect();
+        assert_eq!(ls.len(), 40);
+        assert!(ls.iter().all(|l| l.iter().position(|&v| v == 4) > l.iter().position(|&v| v == 0)));
+    }
+
+    #[test]
+    fn test_graph_map() {
+        let mut g = DiGraphMap::<&str, ()>::new();
+        g.add_edge("b", "c", ());
+        g.add_edge("a", "c", ());
+        g.add_node("d");
+        g.remove_node("a");
+
+        assert_eq!(Graph::size(&&g), 3);
+
+        let mut ts: Vec<_> = super::toposorts(&g).collect();
+        ts.sort();
+        assert_eq!(ts, vec![vec!["b", "c", "d"], vec!["b", "d", "c"], vec!["d", "b", "c"]]);
+    }
+
     #[test]
     fn test_successors() {
         let mut g = DiGraph::<(), ()>::new();
@@ -1,28 +1,110 @@
 use std::{slice, iter};
 
 use super::Graph;
+use crate::Error;
+
+/// What `add_edge` and `try_add_edge` do with an edge that is already present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Duplicates {
+    /// Store it again (parallel edges).
+    #[default]
+    Allow,
+    /// Drop it silently.
+    Ignore,
+    /// Fail with `Error::DuplicateEdge`; `add_edge` panics.
+    Reject,
+}
 
 #[derive(Debug, Clone)]
 pub struct SimpleGraph {
     succ: Vec<Vec<usize>>,
     edge_count: usize,
+    duplicates: Duplicates,
 }
 
 impl SimpleGraph {
     pub fn new(size: usize) -> Self {
+        Self::with_duplicates(size, Duplicates::Allow)
+    }
+
+    pub fn with_duplicates(size: usize, duplicates: Duplicates) -> Self {
         let succ = vec![vec![]; size];
         let edge_count = 0;
-        SimpleGraph { succ, edge_count }
+        SimpleGraph { succ, edge_count, duplicates }
+    }
+
+    pub fn set_duplicates(&mut self, duplicates: Duplicates) {
+        self.duplicates = duplicates;
+    }
+
+    pub fn add_node(&mut self) -> usize {
+        self.succ.push(vec![]);
+        self.succ.len() - 1
     }
 
     pub fn edge_count(&self) -> usize {
         self.edge_count
     }
 
+    /// Panics if either endpoint is out of range, or on a duplicate under `Duplicates::Reject`.
+    /// Self-loops are accepted; enumeration then reports them as cycles.
     pub fn add_edge(&mut self, from: usize, to: usize) {
-        assert!(to < self.size());
+        let size = self.size();
+        assert!(from < size && to < size, "edge {from} -> {to} out of range for graph of size {size}");
+        if let Err(e) = self.insert(from, to) {
+            panic!("{e}");
+        }
+    }
+
+    /// Like `add_edge`, but reports out-of-range endpoints, self-loops and rejected duplicates.
+    pub fn try_add_edge(&mut self, from: usize, to: usize) -> Result<(), Error> {
+        let size = self.size();
+        if let Some(node) = [from, to].into_iter().find(|&v| v >= size) {
+            return Err(Error::NodeOutOfRange { node, size });
+        }
+        if from == to {
+            return Err(Error::SelfLoop(from));
+        }
+        self.insert(from, to)
+    }
+
+    fn insert(&mut self, from: usize, to: usize) -> Result<(), Error> {
+        if self.duplicates != Duplicates::Allow && self.succ[from].contains(&to) {
+            return match self.duplicates {
+                Duplicates::Reject => Err(Error::DuplicateEdge(from, to)),
+                _ => Ok(()),
+            };
+        }
         self.succ[from].push(to);
         self.edge_count += 1;
+        Ok(())
+    }
+}
+
+/// Sized to the largest endpoint. Duplicates are kept, as with `add_edge`.
+impl FromIterator<(usize, usize)> for SimpleGraph {
+    fn from_iter<I: IntoIterator<Item = (usize, usize)>>(iter: I) -> Self {
+        let edges: Vec<_> = iter.into_iter().collect();
+        let size = edges.iter().map(|&(v, w)| v.max(w) + 1).max().unwrap_or(0);
+        let mut g = SimpleGraph::new(size);
+        for (v, w) in edges {
+            g.add_edge(v, w);
+        }
+        g
+    }
+}
+
+/// Sized to the largest endpoint, rejecting self-loops and duplicate edges.
+impl TryFrom<&[(usize, usize)]> for SimpleGraph {
+    type Error = Error;
+
+    fn try_from(edges: &[(usize, usize)]) -> Result<Self, Error> {
+        let size = edges.iter().map(|&(v, w)| v.max(w) + 1).max().unwrap_or(0);
+        let mut g = SimpleGraph::with_duplicates(size, Duplicates::Reject);
+        for &(v, w) in edges {
+            g.try_add_edge(v, w)?;
+        }
+        Ok(g)
     }
 }
 
@@ -62,3 +144,50 @@ impl Graph for SimpleGraph {
         self.succ[v].iter().copied()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_add_edge() {
+        let mut g = SimpleGraph::new(3);
+        assert_eq!(g.try_add_edge(0, 1), Ok(()));
+        assert_eq!(g.try_add_edge(3, 1), Err(Error::NodeOutOfRange { node: 3, size: 3 }));
+        assert_eq!(g.try_add_edge(1, 7), Err(Error::NodeOutOfRange { node: 7, size: 3 }));
+        assert_eq!(g.try_add_edge(2, 2), Err(Error::SelfLoop(2)));
+        assert_eq!(g.try_add_edge(0, 1), Ok(()));
+        assert_eq!(g.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let mut g = SimpleGraph::with_duplicates(2, Duplicates::Ignore);
+        g.add_edge(0, 1);
+        g.add_edge(0, 1);
+        assert_eq!(g.edges().collect::<Vec<_>>(), [(0, 1)]);
+
+        g.set_duplicates(Duplicates::Reject);
+        assert_eq!(g.try_add_edge(0, 1), Err(Error::DuplicateEdge(0, 1)));
+        assert_eq!(g.edge_count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_add_edge_out_of_range() {
+        SimpleGraph::new(2).add_edge(2, 0);
+    }
+
+    #[test]
+    fn test_from_edges() {
+        let g: SimpleGraph = [(0, 2), (1, 2), (0, 2)].into_iter().collect();
+        assert_eq!(g.size(), 3);
+        assert_eq!(g.edge_count(), 3);
+
+        let g = SimpleGraph::try_from(&[(0, 2), (1, 2)][..]).unwrap();
+        assert_eq!(g.edges().collect::<Vec<_>>(), [(0, 2), (1, 2)]);
+        assert_eq!(SimpleGraph::try_from(&[(0, 1), (0, 1)][..]).err(), Some(Error::DuplicateEdge(0, 1)));
+        assert_eq!(SimpleGraph::try_from(&[(1, 1)][..]).err(), Some(Error::SelfLoop(1)));
+        assert_eq!(SimpleGraph::try_from(&[][..]).unwrap().size(), 0);
+    }
+}
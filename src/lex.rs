@@ -0,0 +1,344 @@
+use crate::graph::Graph;
+use crate::graph::simple::SimpleGraph;
+use crate::nrpr::toposort;
+use crate::Error;
+
+use std::collections::BTreeSet;
+
+// Scratch space for stepping a topological sort to its lexicographic neighbour.
+struct Lex {
+    // in-degree of each node within the suffix being examined
+    deg: Vec<usize>,
+    // nodes of the suffix with no predecessor in it
+    sources: BTreeSet<usize>,
+}
+
+impl Lex {
+    fn new(n: usize) -> Self {
+        Lex { deg: vec![0; n], sources: BTreeSet::new() }
+    }
+
+    // Rewrites the shortest suffix of `l` that can be changed to get the next sort
+    // (the previous one if `!forward`), returning where the suffix starts. Returns
+    // `None` if `l` is the last sort, leaving `self` set up for all of `g`.
+    fn step<G: Graph>(&mut self, g: &G, l: &mut [usize], forward: bool) -> Option<usize> {
+        self.sources.clear();
+        for i in (0..l.len()).rev() {
+            let v = l[i];
+            for w in g.successors(v) {
+                if self.deg[w] == 0 {
+                    self.sources.remove(&w);
+                }
+                self.deg[w] += 1;
+            }
+            let next = if forward {
+                self.sources.range(v + 1..).next()
+            } else {
+                self.sources.range(..v).next_back()
+            };
+            if let Some(&c) = next {
+                self.sources.insert(v);
+                self.fill(g, &mut l[i..], c, forward);
+                return Some(i);
+            }
+            self.sources.insert(v);
+        }
+        None
+    }
+
+    // Fills `l` with `c` followed by the smallest (largest if `!forward`) completion
+    // from the current sources.
+    fn fill<G: Graph>(&mut self, g: &G, l: &mut [usize], mut c: usize, forward: bool) {
+        for x in l {
+            self.sources.remove(&c);
+            *x = c;
+            for w in g.successors(c) {
+                self.deg[w] -= 1;
+                if self.deg[w] == 0 {
+                    self.sources.insert(w);
+                }
+            }
+            let next = if forward { self.sources.first() } else { self.sources.last() };
+            if let Some(&next) = next {
+                c = next;
+            }
+        }
+    }
+
+    // Fills `l` with the first sort of `g` (the last if `!forward`). Expects `deg` to
+    // hold the in-degrees and `sources` the minimal nodes of `g`, as left by `step`.
+    fn restart<G: Graph>(&mut self, g: &G, l: &mut [usize], forward: bool) {
+        let first = if forward { self.sources.first() } else { self.sources.last() };
+        if let Some(&c) = first {
+            self.fill(g, l, c, forward);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.deg.fill(0);
+        self.sources.clear();
+    }
+
+    fn first<G: Graph>(&mut self, g: &G, l: &mut [usize], forward: bool) {
+        for (_, w) in g.edges() {
+            self.deg[w] += 1;
+        }
+        self.sources = (0..g.size()).filter(|&v| self.deg[v] == 0).collect();
+        self.restart(g, l, forward);
+    }
+}
+
+/// Enumerates topological sorts in lexicographic order, comparing nodes by index or by
+/// a user-supplied priority.
+///
+/// Each step takes O((n + m) log n) time in the worst case, but most only rewrite a
+/// short suffix of the sort.
+pub struct Enumerator {
+    // the graph with each node relabeled by its rank in the priority order
+    graph: SimpleGraph,
+    lex: Lex,
+    // `l` in terms of ranks
+    order: Vec<usize>,
+    node: Vec<usize>,
+    l: Vec<usize>,
+}
+
+impl Enumerator {
+    /// Panics if the graph has a cycle. See [`Enumerator::try_new`].
+    pub fn new<G: Graph>(g: G) -> Self {
+        Self::try_new(g).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_new<G: Graph>(g: G) -> Result<Self, Error> {
+        Self::try_with_priority(g, |v| v)
+    }
+
+    /// Panics if the graph has a cycle. See [`Enumerator::try_with_priority`].
+    pub fn with_priority<G: Graph, K: Ord>(g: G, key: impl FnMut(usize) -> K) -> Self {
+        Self::try_with_priority(g, key).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Orders sorts lexicographically by `key` of each node, breaking ties by index.
+    pub fn try_with_priority<G: Graph, K: Ord>(g: G, key: impl FnMut(usize) -> K) -> Result<Self, Error> {
+        toposort(&g)?;
+        let n = g.size();
+
+        let keys: Vec<K> = (0..n).map(key).collect();
+        let mut node: Vec<usize> = (0..n).collect();
+        node.sort_by(|&v, &w| keys[v].cmp(&keys[w]));
+        let mut rank = vec![0; n];
+        for (r, &v) in node.iter().enumerate() {
+            rank[v] = r;
+        }
+
+        let mut graph = SimpleGraph::new(n);
+        for (v, w) in g.edges() {
+            graph.add_edge(rank[v], rank[w]);
+        }
+
+        let mut lex = Lex::new(n);
+        let mut order = vec![0; n];
+        lex.first(&graph, &mut order, true);
+        let l = order.iter().map(|&r| node[r]).collect();
+
+        Ok(Enumerator { graph, lex, order, node, l })
+    }
+
+    pub fn current(&self) -> &[usize] {
+        &self.l
+    }
+
+    /// Moves to the next topological sort. Returns `false` once they are exhausted,
+    /// leaving `current` at the last one.
+    pub fn advance(&mut self) -> bool {
+        match self.lex.step(&self.graph, &mut self.order, true) {
+            Some(i) => {
+                for (x, &r) in self.l[i..].iter_mut().zip(&self.order[i..]) {
+                    *x = self.node[r];
+                }
+                true
+            },
+            None => {
+                self.lex.reset();
+                false
+            },
+        }
+    }
+}
+
+impl crate::Enumerator for Enumerator {
+    fn current(&self) -> &[usize] {
+        self.current()
+    }
+
+    fn advance(&mut self) -> bool {
+        self.advance()
+    }
+}
+
+/// Yields all topological sorts in lexicographic order. Panics if the graph has a cycle.
+pub fn toposorts<G: Graph>(g: G) -> impl Iterator<Item = Vec<usize>> {
+    let mut en = Enumerator::new(g);
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let l = en.current().to_vec();
+        done = !en.advance();
+        Some(l)
+    })
+}
+
+fn is_toposort<G: Graph>(g: &G, l: &[usize]) -> bool {
+    let n = g.size();
+    let mut pos = vec![n; n];
+    for (i, &v) in l.iter().enumerate() {
+        if v >= n || pos[v] != n {
+            return false;
+        }
+        pos[v] = i;
+    }
+    l.len() == n && g.edges().all(|(v, w)| pos[v] < pos[w])
+}
+
+fn neighbour<G: Graph>(g: &G, l: &mut [usize], forward: bool) -> bool {
+    assert!(is_toposort(g, l), "not a topological sort of the graph");
+    let mut lex = Lex::new(l.len());
+    if lex.step(g, l, forward).is_some() {
+        true
+    } else {
+        lex.restart(g, l, forward);
+        false
+    }
+}
+
+/// Rewrites `l` in place to the lexicographically next topological sort of `g`, like
+/// C++'s `next_permutation`. If `l` is the last sort, rewrites it to the first one and
+/// returns `false`.
+///
+/// Takes O((n + m) log n) time. Panics if `l` is not a topological sort of `g`.
+pub fn next_toposort<G: Graph>(g: &G, l: &mut [usize]) -> bool {
+    neighbour(g, l, true)
+}
+
+/// Rewrites `l` in place to the lexicographically previous topological sort of `g`. If
+/// `l` is the first sort, rewrites it to the last one and returns `false`.
+///
+/// Takes O((n + m) log n) time. Panics if `l` is not a topological sort of `g`.
+pub fn prev_toposort<G: Graph>(g: &G, l: &mut [usize]) -> bool {
+    neighbour(g, l, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rank::rank;
+    use crate::testing::{check_toposorts, dags};
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+
+    fn sorts<E: crate::Enumerator>(mut en: E) -> Vec<Vec<usize>> {
+        let mut sorts = vec![en.current().to_vec()];
+        while en.advance() {
+            sorts.push(en.current().to_vec());
+        }
+        sorts
+    }
+
+    #[test]
+    fn test_chain_and_antichain() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(2, 1);
+        g.add_edge(1, 0);
+        assert_eq!(toposorts(g).collect::<Vec<_>>(), [[2, 1, 0]]);
+
+        let g = SimpleGraph::new(3);
+        let expected = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        assert_eq!(toposorts(g).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        let en = Enumerator::with_priority(g, std::cmp::Reverse);
+        assert_eq!(sorts(en), [[2, 0, 1], [0, 2, 1], [0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        g.add_edge(1, 0);
+        assert!(matches!(Enumerator::try_new(g), Err(Error::Cycle(_))));
+    }
+
+    #[test]
+    fn test_next_prev() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        let mut l = [0, 1, 2];
+        assert!(next_toposort(&g, &mut l));
+        assert_eq!(l, [0, 2, 1]);
+        assert!(next_toposort(&g, &mut l));
+        assert_eq!(l, [2, 0, 1]);
+        assert!(!next_toposort(&g, &mut l));
+        assert_eq!(l, [0, 1, 2]);
+        assert!(!prev_toposort(&g, &mut l));
+        assert_eq!(l, [2, 0, 1]);
+        assert!(prev_toposort(&g, &mut l));
+        assert_eq!(l, [0, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a topological sort")]
+    fn test_next_invalid() {
+        let mut g = SimpleGraph::new(2);
+        g.add_edge(0, 1);
+        next_toposort(&g, &mut [1, 0]);
+    }
+
+    proptest! {
+        #[test]
+        fn test_next_prev_toposort(g in dags(0..8, 0..14)) {
+            let ls: Vec<_> = toposorts(g.clone()).collect();
+            let mut l = ls[0].clone();
+            for expected in ls.iter().cycle().skip(1).take(ls.len()) {
+                prop_assert_eq!(next_toposort(&g, &mut l), expected != &ls[0]);
+                prop_assert_eq!(&l, expected);
+            }
+            for expected in ls.iter().rev() {
+                prop_assert_eq!(prev_toposort(&g, &mut l), expected != ls.last().unwrap());
+                prop_assert_eq!(&l, expected);
+            }
+        }
+
+        #[test]
+        fn test_toposorts(g in dags(0..8, 0..14)) {
+            let ls: Vec<_> = toposorts(g.clone()).collect();
+            for (i, l) in ls.iter().enumerate() {
+                prop_assert_eq!(rank(&g, l), Some(BigUint::from(i)));
+            }
+            check_toposorts(&g, ls)?;
+        }
+
+        #[test]
+        fn test_engines_agree(g in dags(0..7, 0..10)) {
+            let mut lex = sorts(Enumerator::new(g.clone()));
+            let mut nrpr = sorts(crate::nrpr::Enumerator::new(g));
+            lex.sort();
+            nrpr.sort();
+            prop_assert_eq!(lex, nrpr);
+        }
+
+        #[test]
+        fn test_priority_order(g in dags(0..7, 0..10), seed in any::<u64>()) {
+            let key = |v: usize| (v as u64).wrapping_mul(seed | 1).rotate_left(17);
+            let ls = sorts(Enumerator::with_priority(g.clone(), key));
+            let keyed: Vec<Vec<_>> = ls.iter().map(|l| l.iter().map(|&v| (key(v), v)).collect()).collect();
+            prop_assert!(keyed.windows(2).all(|w| w[0] < w[1]));
+            check_toposorts(&g, ls)?;
+        }
+    }
+}
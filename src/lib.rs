@@ -1,3 +1,22 @@
+pub mod backtracking;
+pub mod constraints;
+pub mod count;
+mod enumerator;
+mod error;
+pub mod generate;
 pub mod glp;
 pub mod graph;
+pub mod lex;
+pub mod loopless;
 pub mod nrpr;
+pub mod optimize;
+pub mod parallel;
+mod prefix;
+pub mod rank;
+pub mod sample;
+pub mod shard;
+pub mod sign;
+pub mod testing;
+
+pub use enumerator::Enumerator;
+pub use error::Error;
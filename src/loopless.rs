@@ -0,0 +1,141 @@
+use crate::glp::GLPIterator;
+use crate::graph::Graph;
+use crate::nrpr::{Move, NRPR};
+use crate::Error;
+
+/// Enumerates topological sorts doing a bounded amount of work for every sort, rather
+/// than a bounded amount on average as [`toposorts`] does.
+///
+/// [`toposorts`] emits the sorts with a positive sign from the signed enumeration of
+/// [`signed_toposorts`], which can take O(n) steps between two of them. Each step of
+/// that enumeration changes the parity of the sort or flips the sign, never both, so
+/// every other signed sort is a different sort and together they cover all of them.
+/// `Loopless` visits those, taking exactly two steps per sort. Consecutive sorts
+/// differ by one or two adjacent swaps. The order differs from [`toposorts`].
+///
+/// Each step is O(1) in the worst case with [`Adjacency::Dense`], and O(log d) for
+/// nodes of degree d with [`Adjacency::Sparse`].
+///
+/// [`toposorts`]: crate::nrpr::toposorts
+/// [`signed_toposorts`]: crate::nrpr::signed_toposorts
+/// [`Adjacency::Dense`]: crate::nrpr::Adjacency::Dense
+/// [`Adjacency::Sparse`]: crate::nrpr::Adjacency::Sparse
+pub struct Loopless {
+    glp: GLPIterator<NRPR>,
+    steps: u64,
+}
+
+impl Loopless {
+    /// Panics if the graph has a cycle. See [`Loopless::try_new`].
+    pub fn new<G: Graph>(g: G) -> Self {
+        Self::try_new(g).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_new<G: Graph>(g: G) -> Result<Self, Error> {
+        NRPR::try_new(g).map(Self::from)
+    }
+
+    pub fn current(&self) -> &[usize] {
+        &self.glp.proc.l
+    }
+
+    /// The position of each node in `current`.
+    pub fn positions(&self) -> &[usize] {
+        self.glp.proc.positions()
+    }
+
+    /// Moves to the next topological sort. Returns `false` once they are exhausted,
+    /// leaving `current` at the last one.
+    pub fn advance(&mut self) -> bool {
+        let Some(m) = self.step() else {
+            return false;
+        };
+        if self.step().is_some() {
+            return true;
+        }
+        // The signed enumeration ended on the other copy of a sort; step back.
+        if let Move::Swap(a, b) = m {
+            self.glp.proc.swap(a, b);
+        }
+        false
+    }
+
+    fn step(&mut self) -> Option<Move> {
+        self.steps += 1;
+        self.glp.next()
+    }
+}
+
+impl From<NRPR> for Loopless {
+    fn from(p: NRPR) -> Self {
+        Loopless { glp: GLPIterator::new(p), steps: 0 }
+    }
+}
+
+impl crate::Enumerator for Loopless {
+    fn current(&self) -> &[usize] {
+        self.current()
+    }
+
+    fn advance(&mut self) -> bool {
+        self.advance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::testing::{check_toposorts, dags};
+    use proptest::prelude::*;
+
+    // The number of adjacent swaps between two orders of the same nodes.
+    fn swap_distance(l: &[usize], m: &[usize]) -> usize {
+        let mut pos = vec![0; m.len()];
+        for (i, &v) in m.iter().enumerate() {
+            pos[v] = i;
+        }
+        (0..l.len()).map(|i| (i + 1..l.len()).filter(|&j| pos[l[i]] > pos[l[j]]).count()).sum()
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(2);
+        g.add_edge(0, 1);
+        g.add_edge(1, 0);
+        assert!(matches!(Loopless::try_new(g), Err(Error::Cycle(_))));
+    }
+
+    #[test]
+    fn test_single() {
+        let mut en = Loopless::new(SimpleGraph::new(1));
+        assert_eq!(en.current(), [0]);
+        assert!(!en.advance());
+        assert_eq!(en.current(), [0]);
+    }
+
+    proptest! {
+        #[test]
+        fn test_loopless(g in dags(0..8, 0..14)) {
+            let mut en = Loopless::new(g.clone());
+            let mut ls = vec![en.current().to_vec()];
+            loop {
+                let steps = en.steps;
+                let more = en.advance();
+                prop_assert!(en.steps - steps <= 2);
+                let l = en.current();
+                for (v, &i) in en.positions().iter().enumerate() {
+                    prop_assert_eq!(l[i], v);
+                }
+                if !more {
+                    prop_assert_eq!(l, ls.last().unwrap().as_slice());
+                    break;
+                }
+                let d = swap_distance(ls.last().unwrap(), l);
+                prop_assert!(d == 1 || d == 2);
+                ls.push(l.to_vec());
+            }
+            check_toposorts(&g, ls)?;
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use crate::glp::{GLPSubProc, states};
+use crate::glp::{GLPIterator, GLPSubProc, states};
 use crate::graph::Graph;
+use crate::sign::is_odd;
 
 use bit_vec::BitVec;
 
+pub use crate::Error;
+
 #[derive(Debug)]
 pub enum Move {
     None,
@@ -20,14 +23,14 @@ fn sorted<T: Ord>(a: T, b: T) -> (T, T) {
 
 fn next_move(has_edge: impl Fn(usize, usize) -> bool, e: bool, s: bool, l: &[usize], i: usize, j: usize) -> Move {
     if j == 1 {
-        if s == !e {
+        if s != e {
             Move::None
         } else if j + 1 >= l.len() || has_edge(l[j], l[j + 1]) {
             Move::FlipSign
         } else {
             Move::Swap(j, j + 1)
         }
-    } else if i == 0 && s == !e {
+    } else if i == 0 && s != e {
         Move::Swap(j, j - 1)
     } else {
         let rw = (j % 2 == 1) != e;
@@ -54,68 +57,223 @@ fn edge_pos(n: usize, v: usize, w: usize) -> usize {
     n * v + w
 }
 
-pub struct NRPR {
-    n: usize,
-    adj: BitVec,
-    k: usize,
-    pub l: Vec<usize>,
-    j: Vec<usize>,
-    ix: Vec<usize>,
-    pub s: Vec<bool>,
-    e: Vec<bool>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Adjacency {
+    /// Whichever of `Dense` and `Sparse` takes less memory for the graph.
+    #[default]
+    Auto,
+    /// An `n * n` bit matrix.
+    Dense,
+    /// Sorted successor lists, taking O(n + m) memory.
+    Sparse,
 }
 
-impl NRPR {
-    pub fn new<G: Graph>(g: G) -> Self {
+pub(crate) enum Adj {
+    Dense(usize, BitVec),
+    Sparse(Vec<usize>, Vec<usize>),
+}
+
+impl Adj {
+    pub(crate) fn new<G: Graph>(g: &G, kind: Adjacency) -> Self {
         let n = g.size();
 
-        let mut adj = BitVec::from_elem(n * n, false);
+        let mut start = vec![0; n + 1];
+        for (v, _) in g.edges() {
+            start[v + 1] += 1;
+        }
+        for v in 0..n {
+            start[v + 1] += start[v];
+        }
+        let m = start[n];
 
-        let mut l0 = Vec::with_capacity(n);
-        let mut j = vec![];
-        let mut min = vec![];
-        let mut in_deg = vec![0; n];
+        let dense = match kind {
+            Adjacency::Dense => true,
+            Adjacency::Sparse => false,
+            Adjacency::Auto => {
+                let words = n + 1 + m;
+                n.checked_mul(n).is_some_and(|bits| bits <= words.saturating_mul(usize::BITS as usize))
+            },
+        };
 
-        for (v, w) in g.edges() {
-            adj.set(edge_pos(n, v, w), true);
-            in_deg[w] += 1;
+        if dense {
+            let size = n.checked_mul(n).expect("graph too large for dense adjacency");
+            let mut adj = BitVec::from_elem(size, false);
+            for (v, w) in g.edges() {
+                adj.set(edge_pos(n, v, w), true);
+            }
+            Adj::Dense(n, adj)
+        } else {
+            let mut succ = vec![0; m];
+            let mut next = start.clone();
+            for (v, w) in g.edges() {
+                succ[next[v]] = w;
+                next[v] += 1;
+            }
+            for v in 0..n {
+                succ[start[v]..start[v + 1]].sort_unstable();
+            }
+            Adj::Sparse(start, succ)
         }
+    }
 
-        for (v, &d) in in_deg.iter().enumerate() {
-            if d == 0 {
-                min.push(v);
-            }
+    pub(crate) fn has_edge(&self, v: usize, w: usize) -> bool {
+        match self {
+            Adj::Dense(n, adj) => adj[edge_pos(*n, v, w)],
+            Adj::Sparse(start, succ) => succ[start[v]..start[v + 1]].binary_search(&w).is_ok(),
         }
+    }
+}
 
-        while let Some(a) = min.pop() {
-            l0.push(a);
+// Every node left over by the peel still has a predecessor that was left over,
+// so walking backwards through them must eventually revisit a node.
+fn find_cycle<G: Graph>(g: &G, in_deg: &[usize]) -> Vec<usize> {
+    let n = in_deg.len();
+    let mut pred = vec![n; n];
+
+    for (v, w) in g.edges() {
+        if in_deg[v] > 0 && in_deg[w] > 0 {
+            pred[w] = v;
+        }
+    }
 
-            let b = min.pop();
+    let mut seen = vec![false; n];
+    let mut walk = vec![];
+    let mut v = in_deg.iter().position(|&d| d > 0).unwrap();
 
-            if let Some(b) = b {
-                let i = l0.len();
-                j.push(i - 1);
-                j.push(i);
-                l0.push(b);
-            }
+    while !seen[v] {
+        seen[v] = true;
+        walk.push(v);
+        v = pred[v];
+    }
 
-            for v in [Some(a), b].into_iter().flatten() {
-                for w in g.successors(v) {
-                    in_deg[w] -= 1;
-                    if in_deg[w] == 0 {
-                        min.push(w);
-                    }
+    let start = walk.iter().position(|&w| w == v).unwrap();
+    let mut cycle = walk.split_off(start);
+    cycle.reverse();
+    cycle.rotate_right(1);
+    cycle
+}
+
+// Kahn's algorithm, taking minimal elements two at a time. Also returns the
+// positions of each pair taken together.
+fn peel<G: Graph>(g: &G) -> Result<(Vec<usize>, Vec<usize>), Error> {
+    let n = g.size();
+
+    let mut l0 = Vec::with_capacity(n);
+    let mut j = vec![];
+    let mut min = vec![];
+    let mut in_deg = vec![0; n];
+
+    for (v, w) in g.edges() {
+        if let Some(node) = [v, w].into_iter().find(|&x| x >= n) {
+            return Err(Error::NodeOutOfRange { node, size: n });
+        }
+        in_deg[w] += 1;
+    }
+
+    for (v, &d) in in_deg.iter().enumerate() {
+        if d == 0 {
+            min.push(v);
+        }
+    }
+
+    while let Some(a) = min.pop() {
+        l0.push(a);
+
+        let b = min.pop();
+
+        if let Some(b) = b {
+            let i = l0.len();
+            j.push(i - 1);
+            j.push(i);
+            l0.push(b);
+        }
+
+        for v in [Some(a), b].into_iter().flatten() {
+            for w in g.successors(v) {
+                in_deg[w] -= 1;
+                if in_deg[w] == 0 {
+                    min.push(w);
                 }
             }
         }
+    }
+
+    if l0.len() < n {
+        return Err(Error::Cycle(find_cycle(g, &in_deg)));
+    }
+
+    Ok((l0, j))
+}
+
+pub(crate) fn toposort<G: Graph>(g: &G) -> Result<Vec<usize>, Error> {
+    peel(g).map(|(l, _)| l)
+}
+
+fn inverse(l: &[usize]) -> Vec<usize> {
+    let mut pos = vec![0; l.len()];
+    for (i, &v) in l.iter().enumerate() {
+        pos[v] = i;
+    }
+    pos
+}
+
+fn swap(l: &mut [usize], pos: &mut [usize], a: usize, b: usize) {
+    l.swap(a, b);
+    pos[l[a]] = a;
+    pos[l[b]] = b;
+}
+
+pub struct NRPR {
+    adj: Adj,
+    k: usize,
+    pub l: Vec<usize>,
+    pos: Vec<usize>,
+    j: Vec<usize>,
+    ix: Vec<usize>,
+    pub s: Vec<bool>,
+    e: Vec<bool>,
+}
+
+impl NRPR {
+    /// Panics if the graph has a cycle. See [`NRPR::try_new`].
+    pub fn new<G: Graph>(g: G) -> Self {
+        Self::try_new(g).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_new<G: Graph>(g: G) -> Result<Self, Error> {
+        Self::try_with_adjacency(g, Adjacency::Auto)
+    }
+
+    pub fn try_with_adjacency<G: Graph>(g: G, adjacency: Adjacency) -> Result<Self, Error> {
+        let (l0, j) = peel(&g)?;
+        let adj = Adj::new(&g, adjacency);
 
         let k = j.len() / 2;
         let l = l0;
+        let pos = inverse(&l);
         let ix = j.clone();
         let s = vec![true; k + 1];
         let e = vec![true; k];
 
-        NRPR { n, adj, k, l, j, ix, s, e }
+        Ok(NRPR { adj, k, l, pos, j, ix, s, e })
+    }
+
+    /// The position of each node in `l`.
+    pub fn positions(&self) -> &[usize] {
+        &self.pos
+    }
+
+    pub fn position(&self, v: usize) -> usize {
+        self.pos[v]
+    }
+
+    pub(crate) fn swap(&mut self, a: usize, b: usize) {
+        swap(&mut self.l, &mut self.pos, a, b);
+    }
+
+    /// Whether `v` comes before `w` in `l`.
+    pub fn is_before(&self, v: usize, w: usize) -> bool {
+        self.pos[v] < self.pos[w]
     }
 }
 
@@ -128,10 +286,10 @@ impl GLPSubProc for NRPR {
 
     fn execute(&mut self, i: usize) -> (bool, Self::Delta) {
         let &mut NRPR {
-            n,
             k,
             ref adj,
             ref mut l,
+            ref mut pos,
             ref mut j,
             ref mut ix,
             ref mut s,
@@ -141,7 +299,7 @@ impl GLPSubProc for NRPR {
         if i == k {
             let m = if k > 0 {
                 let p = 2 * k - 2;
-                l.swap(ix[p], ix[p + 1]);
+                swap(l, pos, ix[p], ix[p + 1]);
                 ix.swap(p, p + 1);
                 Move::Swap(ix[p], ix[p + 1])
             } else {
@@ -153,13 +311,13 @@ impl GLPSubProc for NRPR {
             let ji = j[2 * i];
             let (i1, i2) = sorted(ix[2 * i], ix[2 * i + 1]);
 
-            let m = next_move(|v, w| adj[edge_pos(n, v, w)], e[i], s[i], &l[ji..], i1 - ji, i2 - ji);
+            let m = next_move(|v, w| adj.has_edge(v, w), e[i], s[i], &l[ji..], i1 - ji, i2 - ji);
 
             let m = match m {
                 Move::Swap(a, b) => {
                     let a = ji + a;
                     let b = ji + b;
-                    l.swap(a, b);
+                    swap(l, pos, a, b);
                     if a == ix[2 * i] {
                         ix[2 * i] = b;
                     }
@@ -172,7 +330,7 @@ impl GLPSubProc for NRPR {
                     s[i] = !s[i];
                     if i > 0 {
                         let p = 2 * i - 2;
-                        l.swap(ix[p], ix[p + 1]);
+                        swap(l, pos, ix[p], ix[p + 1]);
                         ix.swap(p, p + 1);
                         Move::Swap(ix[p], ix[p + 1])
                     } else {
@@ -198,14 +356,301 @@ impl GLPSubProc for NRPR {
     }
 }
 
+/// Receives the changes made by an [`Enumerator`] as it steps, so that state derived
+/// from the current sort can be kept up to date without looking at the whole sort.
+pub trait Observer {
+    /// The nodes at `pos` and `pos + 1` were swapped, leaving `left` at `pos` and
+    /// `right` at `pos + 1`. Not every swap results in a new sort.
+    fn on_adjacent_swap(&mut self, _pos: usize, _left: usize, _right: usize) {}
+
+    /// The enumerator reached a new topological sort.
+    fn on_emit(&mut self, _l: &[usize]) {}
+}
+
+impl Observer for () {}
+
+/// Cursor over the topological sorts of a graph, in the same order as [`toposorts`],
+/// that steps in place without allocating.
+pub struct Enumerator {
+    glp: GLPIterator<NRPR>,
+    odd: bool,
+}
+
+impl Enumerator {
+    pub fn new<G: Graph>(g: G) -> Self {
+        Self::from(NRPR::new(g))
+    }
+
+    pub fn try_new<G: Graph>(g: G) -> Result<Self, Error> {
+        NRPR::try_new(g).map(Self::from)
+    }
+
+    pub fn current(&self) -> &[usize] {
+        &self.glp.proc.l
+    }
+
+    /// The position of each node in `current`.
+    pub fn positions(&self) -> &[usize] {
+        self.glp.proc.positions()
+    }
+
+    pub fn position(&self, v: usize) -> usize {
+        self.glp.proc.position(v)
+    }
+
+    /// Whether `v` comes before `w` in `current`.
+    pub fn is_before(&self, v: usize, w: usize) -> bool {
+        self.glp.proc.is_before(v, w)
+    }
+
+    /// The sign of `current` in the signed enumeration driven by [`Enumerator::step`].
+    /// Always `true` after [`Enumerator::advance`].
+    pub fn sign(&self) -> bool {
+        self.glp.proc.s[0]
+    }
+
+    /// Moves to the next topological sort. Returns `false` once they are exhausted,
+    /// leaving `current` at the last one.
+    pub fn advance(&mut self) -> bool {
+        self.advance_with(&mut ())
+    }
+
+    /// Like [`Enumerator::advance`], reporting every swap on the way and the new sort
+    /// to `obs`.
+    pub fn advance_with<O: Observer + ?Sized>(&mut self, obs: &mut O) -> bool {
+        while let Some(m) = self.step() {
+            let NRPR { ref l, ref s, .. } = self.glp.proc;
+            if let Move::Swap(a, b) = m {
+                let pos = a.min(b);
+                obs.on_adjacent_swap(pos, l[pos], l[pos + 1]);
+            }
+            if s[0] {
+                obs.on_emit(l);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Takes a single step of the underlying signed enumeration, as in [`glp::deltas`].
+    /// Afterwards `current` may be a repeat of an earlier sort.
+    ///
+    /// [`glp::deltas`]: crate::glp::deltas
+    pub fn step(&mut self) -> Option<Move> {
+        let m = self.glp.next()?;
+        if let Move::Swap(..) = m {
+            self.odd = !self.odd;
+        }
+        Some(m)
+    }
+
+    /// Whether `current` is an odd permutation of the nodes, as in [`is_odd`]. Kept up to
+    /// date in O(1) per step.
+    ///
+    /// [`is_odd`]: crate::sign::is_odd
+    pub fn is_odd(&self) -> bool {
+        self.odd
+    }
+
+    /// Serializes the state of the enumeration. See [`Enumerator::resume`].
+    pub fn checkpoint(&self) -> Vec<u8> {
+        let NRPR { k, ref l, ref ix, ref s, ref e, .. } = self.glp.proc;
+
+        let mut out = Vec::from(CHECKPOINT_MAGIC);
+        out.push(CHECKPOINT_VERSION);
+        put(&mut out, l.len());
+        put(&mut out, k);
+        for &x in l.iter().chain(ix).chain(self.glp.focus()) {
+            put(&mut out, x);
+        }
+        let bits: BitVec = s.iter().chain(e).copied().collect();
+        out.extend(bits.to_bytes());
+        let sum = checksum(&out);
+        out.extend(sum.to_le_bytes());
+        out
+    }
+
+    /// Continues an enumeration of `g` from a checkpoint, with `current` at the same
+    /// sort as when the checkpoint was taken.
+    pub fn resume<G: Graph>(g: G, checkpoint: &[u8]) -> Result<Self, Error> {
+        let bad = Error::InvalidCheckpoint;
+
+        let mut r = checkpoint.strip_prefix(CHECKPOINT_MAGIC).ok_or(bad("not a checkpoint"))?;
+        match r.split_first() {
+            Some((&CHECKPOINT_VERSION, rest)) => r = rest,
+            _ => return Err(bad("unsupported version")),
+        }
+
+        let body = r.len().checked_sub(8).ok_or(bad("wrong length"))?;
+        let (rest, sum) = r.split_at(body);
+        if checksum(&checkpoint[..checkpoint.len() - 8]).to_le_bytes() != sum {
+            return Err(bad("checksum mismatch"));
+        }
+        r = rest;
+
+        let n = take(&mut r)?;
+        let k = take(&mut r)?;
+        if n != g.size() || k > n / 2 {
+            return Err(bad("graph does not match"));
+        }
+
+        let l = (0..n).map(|_| take(&mut r)).collect::<Result<Vec<_>, _>>()?;
+        let ix = (0..2 * k).map(|_| take(&mut r)).collect::<Result<Vec<_>, _>>()?;
+        let p = (0..k + 1).map(|_| take(&mut r)).collect::<Result<Vec<_>, _>>()?;
+        if r.len() != (2 * k + 1).div_ceil(8) {
+            return Err(bad("wrong length"));
+        }
+        let bits = BitVec::from_bytes(r);
+        let s: Vec<bool> = bits.iter().take(k + 1).collect();
+        let e: Vec<bool> = bits.iter().skip(k + 1).take(k).collect();
+
+        let mut pos = vec![n; n];
+        for (i, &v) in l.iter().enumerate() {
+            if v >= n || pos[v] < n {
+                return Err(bad("not a permutation"));
+            }
+            pos[v] = i;
+        }
+        if g.edges().any(|(v, w)| pos[w] < pos[v]) {
+            return Err(bad("not a topological sort"));
+        }
+        // A focus pointer either points at its own index (0) or past it, skipping
+        // only indices that point at themselves.
+        for (i, &x) in p.iter().enumerate() {
+            if x != 0 && (x <= i || x > k + 1 || p[i + 1..x].iter().any(|&y| y != 0)) {
+                return Err(bad("focus pointer out of range"));
+            }
+        }
+
+        let fresh = NRPR::try_new(g)?;
+        if fresh.k != k {
+            return Err(bad("graph does not match"));
+        }
+        if ix != pair_positions(&fresh.l, &fresh.j, &pos) {
+            return Err(bad("pair positions do not match the sort"));
+        }
+        // A pair back at its home positions has already had `e` brought in line
+        // with `s`; NRPR has no move from there otherwise.
+        for i in 0..k {
+            if sorted(ix[2 * i], ix[2 * i + 1]) == (fresh.j[2 * i], fresh.j[2 * i] + 1) && s[i] != e[i] {
+                return Err(bad("sign does not match the pair"));
+            }
+        }
+
+        let proc = NRPR { l, pos, ix, s, e, ..fresh };
+        let odd = is_odd(&proc.l);
+        Ok(Enumerator { glp: GLPIterator::resume(proc, p), odd })
+    }
+}
+
+impl crate::Enumerator for Enumerator {
+    fn current(&self) -> &[usize] {
+        self.current()
+    }
+
+    fn advance(&mut self) -> bool {
+        self.advance()
+    }
+}
+
+impl From<NRPR> for Enumerator {
+    fn from(p: NRPR) -> Self {
+        let odd = is_odd(&p.l);
+        Enumerator { glp: GLPIterator::new(p), odd }
+    }
+}
+
+// Where each paired node sits in the sort with positions `pos` once the nodes peeled
+// before its pair are moved back to the front, which is what `ix` tracks. Counts the
+// later nodes before each one with a Fenwick tree over positions.
+fn pair_positions(l0: &[usize], j: &[usize], pos: &[usize]) -> Vec<usize> {
+    let n = l0.len();
+    let mut tree = vec![0; n + 1];
+    let mut ix = vec![0; j.len()];
+    let mut end = n;
+
+    for i in (0..j.len() / 2).rev() {
+        let base = j[2 * i];
+        for &v in &l0[base..end] {
+            let mut x = pos[v] + 1;
+            while x <= n {
+                tree[x] += 1;
+                x += x & x.wrapping_neg();
+            }
+        }
+        end = base;
+
+        for t in [2 * i, 2 * i + 1] {
+            let mut x = pos[l0[j[t]]];
+            let mut before = 0;
+            while x > 0 {
+                before += tree[x];
+                x &= x - 1;
+            }
+            ix[t] = base + before;
+        }
+    }
+    ix
+}
+
+const CHECKPOINT_MAGIC: &[u8] = b"TGCK";
+const CHECKPOINT_VERSION: u8 = 2;
+
+// FNV-1a over everything before the checksum itself.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn put(out: &mut Vec<u8>, x: usize) {
+    out.extend((x as u64).to_le_bytes());
+}
+
+fn take(r: &mut &[u8]) -> Result<usize, Error> {
+    if r.len() < 8 {
+        return Err(Error::InvalidCheckpoint("wrong length"));
+    }
+    let (x, rest) = r.split_at(8);
+    *r = rest;
+    usize::try_from(u64::from_le_bytes(x.try_into().unwrap())).map_err(|_| Error::InvalidCheckpoint("value out of range"))
+}
+
+/// Drives `obs` through all the topological sorts of a graph, in the same order as
+/// [`toposorts`]. Consecutive sorts differ by a few adjacent swaps.
+pub fn observe<G: Graph, O: Observer + ?Sized>(g: G, obs: &mut O) {
+    let mut en = Enumerator::new(g);
+    obs.on_emit(en.current());
+    while en.advance_with(obs) {}
+}
+
 pub fn toposorts<G: Graph>(g: G) -> impl Iterator<Item = Vec<usize>> {
     states(NRPR::new(g)).filter_map(|s| if s.s[0] { Some(s.l.clone()) } else { None })
 }
 
+/// Like [`toposorts`], but yields the position of each node in the sort rather than the
+/// node at each position.
+pub fn toposorts_positions<G: Graph>(g: G) -> impl Iterator<Item = Vec<usize>> {
+    states(NRPR::new(g)).filter_map(|s| if s.s[0] { Some(s.pos.clone()) } else { None })
+}
+
+/// Yields every signed topological sort `(l, sign)` exactly once, starting with the
+/// first sort of [`toposorts`] with sign `true`. Every sort occurs once with each sign.
+///
+/// Consecutive items differ by exactly one change: either a swap of two adjacent nodes
+/// with the same sign, or the same sort with the sign flipped.
+pub fn signed_toposorts<G: Graph>(g: G) -> impl Iterator<Item = (Vec<usize>, bool)> {
+    states(NRPR::new(g)).map(|s| (s.l.clone(), s.s[0]))
+}
+
+pub fn try_toposorts<G: Graph>(g: G) -> Result<impl Iterator<Item = Vec<usize>>, Error> {
+    let p = NRPR::try_new(g)?;
+    Ok(states(p).filter_map(|s| if s.s[0] { Some(s.l.clone()) } else { None }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::graph::simple::SimpleGraph;
+    use crate::testing::{check_toposorts, dags};
     use proptest::prelude::*;
     use std::collections::HashSet;
 
@@ -254,4 +699,381 @@ mod tests {
             assert_eq!(ts.len(), factorial(n) - factorial(n - 2) * binomial(n, n - 2))
         }
     }
+
+    #[test]
+    fn test_enumerator() {
+        let mut g = SimpleGraph::new(6);
+        g.add_edge(0, 3);
+        g.add_edge(1, 3);
+        g.add_edge(2, 5);
+
+        let mut ts = toposorts(g.clone());
+        let mut en = Enumerator::new(g);
+        loop {
+            assert_eq!(ts.next().as_deref(), Some(en.current()));
+            if !en.advance() {
+                break;
+            }
+        }
+        assert_eq!(ts.next(), None);
+        assert!(!en.advance());
+    }
+
+    #[test]
+    fn test_signed_toposorts() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        let ss: Vec<_> = signed_toposorts(g.clone()).collect();
+        assert_eq!(ss.len(), 6);
+        assert_eq!(ss[0], (toposorts(g).next().unwrap(), true));
+        assert_eq!(signed_toposorts(SimpleGraph::new(1)).collect::<Vec<_>>(), [(vec![0], true), (vec![0], false)]);
+    }
+
+    #[test]
+    fn test_positions() {
+        let mut g = SimpleGraph::new(6);
+        g.add_edge(0, 3);
+        g.add_edge(1, 3);
+        g.add_edge(2, 5);
+
+        let mut en = Enumerator::new(g.clone());
+        let mut ps = toposorts_positions(g);
+        loop {
+            let l = en.current();
+            assert_eq!(en.positions(), inverse(l));
+            assert_eq!(ps.next().as_deref(), Some(en.positions()));
+            for (i, &v) in l.iter().enumerate() {
+                assert_eq!(en.position(v), i);
+            }
+            assert!(en.is_before(0, 3) && en.is_before(2, 5));
+            if !en.advance() {
+                break;
+            }
+        }
+        assert_eq!(ps.next(), None);
+    }
+
+    #[test]
+    fn test_observer() {
+        // Keeps the position of each node up to date from the swaps alone.
+        struct Positions {
+            pos: Vec<usize>,
+            emitted: Vec<Vec<usize>>,
+        }
+
+        impl Observer for Positions {
+            fn on_adjacent_swap(&mut self, pos: usize, left: usize, right: usize) {
+                assert_eq!((self.pos[left], self.pos[right]), (pos + 1, pos));
+                self.pos.swap(left, right);
+            }
+
+            fn on_emit(&mut self, l: &[usize]) {
+                if self.emitted.is_empty() {
+                    for (i, &v) in l.iter().enumerate() {
+                        self.pos[v] = i;
+                    }
+                }
+                for (i, &v) in l.iter().enumerate() {
+                    assert_eq!(self.pos[v], i);
+                }
+                self.emitted.push(l.to_vec());
+            }
+        }
+
+        let mut g = SimpleGraph::new(6);
+        g.add_edge(0, 3);
+        g.add_edge(1, 3);
+        g.add_edge(2, 5);
+
+        let mut obs = Positions { pos: vec![0; 6], emitted: vec![] };
+        observe(g.clone(), &mut obs);
+        assert_eq!(obs.emitted, toposorts(g).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut g = SimpleGraph::new(7);
+        g.add_edge(0, 3);
+        g.add_edge(1, 3);
+        g.add_edge(2, 5);
+        g.add_edge(4, 6);
+
+        let all: Vec<_> = toposorts(g.clone()).collect();
+
+        for stop in [0, 1, 17, all.len() - 1] {
+            let mut en = Enumerator::new(g.clone());
+            for _ in 0..stop {
+                assert!(en.advance());
+            }
+            let checkpoint = en.checkpoint();
+            drop(en);
+
+            let mut en = Enumerator::resume(g.clone(), &checkpoint).unwrap();
+            let mut rest = vec![en.current().to_vec()];
+            while en.advance() {
+                rest.push(en.current().to_vec());
+            }
+            assert_eq!(rest, &all[stop..]);
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_mid_step() {
+        let g = SimpleGraph::new(5);
+
+        let mut en = Enumerator::new(g.clone());
+        for _ in 0..41 {
+            en.step();
+        }
+        let mut resumed = Enumerator::resume(g, &en.checkpoint()).unwrap();
+
+        while let Some(m) = en.step() {
+            let m1 = resumed.step().unwrap();
+            assert_eq!(format!("{m:?}"), format!("{m1:?}"));
+            assert_eq!(en.current(), resumed.current());
+        }
+        assert!(resumed.step().is_none());
+    }
+
+    #[test]
+    fn test_invalid_checkpoint() {
+        let mut g = SimpleGraph::new(4);
+        g.add_edge(0, 1);
+
+        let checkpoint = Enumerator::new(g.clone()).checkpoint();
+        assert!(Enumerator::resume(g.clone(), &checkpoint).is_ok());
+
+        assert!(matches!(Enumerator::resume(g.clone(), b"nope"), Err(Error::InvalidCheckpoint(_))));
+        assert!(matches!(Enumerator::resume(g.clone(), &checkpoint[..checkpoint.len() - 1]), Err(Error::InvalidCheckpoint(_))));
+        assert!(matches!(Enumerator::resume(SimpleGraph::new(5), &checkpoint), Err(Error::InvalidCheckpoint(_))));
+
+        let mut h = SimpleGraph::new(4);
+        h.add_edge(1, 0);
+        assert!(matches!(Enumerator::resume(h, &checkpoint), Err(Error::InvalidCheckpoint(_))));
+    }
+
+    // Re-signs a tampered checkpoint, so that it gets past the checksum.
+    fn reseal(mut c: Vec<u8>) -> Vec<u8> {
+        let end = c.len() - 8;
+        let sum = checksum(&c[..end]);
+        c[end..].copy_from_slice(&sum.to_le_bytes());
+        c
+    }
+
+    #[test]
+    fn test_tampered_checkpoint() {
+        let mut g = SimpleGraph::new(4);
+        g.add_edge(0, 1);
+
+        let mut en = Enumerator::new(g.clone());
+        en.advance();
+        let checkpoint = en.checkpoint();
+        assert!(Enumerator::resume(g.clone(), &checkpoint).is_ok());
+
+        // magic, version, n, k and l come before ix, which comes before p
+        let k = u64::from_le_bytes(checkpoint[13..21].try_into().unwrap()) as usize;
+        assert_eq!(k, 1);
+        let ix = 5 + 16 + 8 * 4;
+        let p = ix + 8 * 2 * k;
+        let set = |at: usize, x: u64| {
+            let mut c = checkpoint.clone();
+            c[at..at + 8].copy_from_slice(&x.to_le_bytes());
+            c
+        };
+
+        let set = |at, x| reseal(set(at, x));
+
+        for (i, x) in [(0, 3), (0, 2), (1, 0), (1, 3)] {
+            let c = set(ix + 8 * i, x);
+            if c != checkpoint {
+                assert_eq!(Enumerator::resume(g.clone(), &c).err(), Some(Error::InvalidCheckpoint("pair positions do not match the sort")));
+            }
+        }
+        for (i, x) in [(0, 3), (1, 1), (1, 5)] {
+            let c = set(p + 8 * i, x);
+            assert_eq!(Enumerator::resume(g.clone(), &c).err(), Some(Error::InvalidCheckpoint("focus pointer out of range")));
+        }
+
+        let mut c = checkpoint.clone();
+        c[ix] ^= 1;
+        assert_eq!(Enumerator::resume(g.clone(), &c).err(), Some(Error::InvalidCheckpoint("checksum mismatch")));
+
+        // Fresh, the pair is at home with s[0] == e[0], so flipping either is caught
+        // even past the checksum; s[1] is only ever cleared.
+        let fresh = Enumerator::new(g.clone()).checkpoint();
+        let bits = fresh.len() - 9;
+        for b in [0, 2] {
+            let mut c = fresh.clone();
+            c[bits] ^= 0x80 >> b;
+            assert_eq!(Enumerator::resume(g.clone(), &c).err(), Some(Error::InvalidCheckpoint("checksum mismatch")));
+            assert_eq!(Enumerator::resume(g.clone(), &reseal(c)).err(), Some(Error::InvalidCheckpoint("sign does not match the pair")));
+        }
+    }
+
+    #[test]
+    fn test_tampered_signs() {
+        let mut g = SimpleGraph::new(7);
+        g.add_edge(0, 3);
+        g.add_edge(1, 3);
+        g.add_edge(2, 5);
+        g.add_edge(4, 6);
+
+        for stop in [0, 1, 5, 17] {
+            let mut en = Enumerator::new(g.clone());
+            for _ in 0..stop {
+                en.advance();
+            }
+            let checkpoint = en.checkpoint();
+
+            // The s and e bits come right before the checksum.
+            let k = u64::from_le_bytes(checkpoint[13..21].try_into().unwrap()) as usize;
+            let bits = checkpoint.len() - 8 - (2 * k + 1).div_ceil(8);
+            for mask in 1..1u32 << (2 * k + 1) {
+                let mut c = checkpoint.clone();
+                for b in (0..2 * k + 1).filter(|b| mask >> b & 1 == 1) {
+                    c[bits + b / 8] ^= 0x80 >> (b % 8);
+                }
+                assert_eq!(Enumerator::resume(g.clone(), &c).err(), Some(Error::InvalidCheckpoint("checksum mismatch")));
+
+                // Past the checksum, a flip is caught only when it leaves the state
+                // inconsistent, but an accepted one must still run to the end.
+                if let Ok(mut en) = Enumerator::resume(g.clone(), &reseal(c)) {
+                    while en.advance() {
+                        assert!(g.edges().all(|(v, w)| en.is_before(v, w)), "stop {stop}, mask {mask:b}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        struct Bad;
+
+        impl Graph for Bad {
+            type Edges<'a> = std::option::IntoIter<(usize, usize)>;
+            type Successors<'a> = std::option::IntoIter<usize>;
+
+            fn size(&self) -> usize {
+                2
+            }
+
+            fn edges(&self) -> Self::Edges<'_> {
+                Some((0, 5)).into_iter()
+            }
+
+            fn successors(&self, v: usize) -> Self::Successors<'_> {
+                if v == 0 { Some(5) } else { None }.into_iter()
+            }
+        }
+
+        assert_eq!(NRPR::try_new(Bad).err(), Some(Error::NodeOutOfRange { node: 5, size: 2 }));
+    }
+
+    fn assert_cycle(g: &SimpleGraph, cycle: &[usize]) {
+        assert!(!cycle.is_empty());
+        for (i, &v) in cycle.iter().enumerate() {
+            let w = cycle[(i + 1) % cycle.len()];
+            assert!(g.successors(v).any(|u| u == w), "missing edge {v} -> {w}");
+        }
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(5);
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+        g.add_edge(2, 3);
+        g.add_edge(3, 1);
+        g.add_edge(3, 4);
+
+        match NRPR::try_new(g.clone()) {
+            Err(Error::Cycle(c)) => {
+                assert_eq!(c.len(), 3);
+                assert_cycle(&g, &c);
+            },
+            _ => panic!("cycle not detected"),
+        }
+
+        assert!(try_toposorts(g).is_err());
+    }
+
+    #[test]
+    fn test_self_loop() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        g.add_edge(2, 2);
+
+        assert_eq!(NRPR::try_new(g).err(), Some(Error::Cycle(vec![2])));
+    }
+
+    proptest! {
+        #[test]
+        fn test_signed_gray_code(g in dags(0..8, 0..14)) {
+            let ss: Vec<_> = signed_toposorts(g.clone()).collect();
+            for w in ss.windows(2) {
+                let ((l, s), (m, t)) = (&w[0], &w[1]);
+                let diff: Vec<_> = (0..l.len()).filter(|&i| l[i] != m[i]).collect();
+                if s == t {
+                    prop_assert!(diff.len() == 2 && diff[1] == diff[0] + 1);
+                    prop_assert_eq!((l[diff[0]], l[diff[1]]), (m[diff[1]], m[diff[0]]));
+                } else {
+                    prop_assert!(diff.is_empty());
+                }
+            }
+            let unique: HashSet<_> = ss.iter().collect();
+            prop_assert_eq!(unique.len(), ss.len());
+            let (pos, neg): (Vec<_>, Vec<_>) = ss.into_iter().partition(|&(_, s)| s);
+            check_toposorts(&g, pos.into_iter().map(|(l, _)| l))?;
+            check_toposorts(&g, neg.into_iter().map(|(l, _)| l))?;
+        }
+
+        #[test]
+        fn test_checkpoint_every_step(g in dags(0..8, 0..14)) {
+            let mut en = Enumerator::new(g.clone());
+            loop {
+                let resumed = Enumerator::resume(g.clone(), &en.checkpoint());
+                prop_assert_eq!(resumed.map(|r| r.current().to_vec()), Ok(en.current().to_vec()));
+                if en.step().is_none() {
+                    break;
+                }
+            }
+        }
+
+        #[test]
+        fn test_parity(g in dags(0..8, 0..14)) {
+            let mut en = Enumerator::new(g);
+            prop_assert_eq!(en.is_odd(), is_odd(en.current()));
+            while en.step().is_some() {
+                prop_assert_eq!(en.is_odd(), is_odd(en.current()));
+            }
+        }
+
+        #[test]
+        fn test_toposorts(g in dags(0..8, 0..14)) {
+            check_toposorts(&g, toposorts(g.clone()))?;
+        }
+
+        #[test]
+        fn test_sparse_matches_dense(g in dags(1..8, 0..12)) {
+            let dense = states(NRPR::try_with_adjacency(g.clone(), Adjacency::Dense).unwrap()).map(|s| s.l.clone());
+            let sparse = states(NRPR::try_with_adjacency(g, Adjacency::Sparse).unwrap()).map(|s| s.l.clone());
+            prop_assert!(dense.eq(sparse));
+        }
+
+        #[test]
+        fn test_random_cycle(n in 2..8usize, edges in prop::collection::vec((0..8usize, 0..8usize), 1..16)) {
+            let mut g = SimpleGraph::new(n);
+            for (v, w) in edges {
+                g.add_edge(v % n, w % n);
+            }
+            g.add_edge(n - 1, 0);
+            g.add_edge(0, n - 1);
+
+            match NRPR::try_new(g.clone()) {
+                Err(Error::Cycle(c)) => assert_cycle(&g, &c),
+                _ => panic!("cycle not detected"),
+            }
+        }
+    }
 }
@@ -0,0 +1,151 @@
+use crate::graph::Graph;
+
+use std::cmp::Ordering;
+
+/// A cost to minimize over topological sorts.
+pub trait Objective {
+    type Cost: PartialOrd;
+
+    /// The cost of a complete sort.
+    fn cost(&mut self, l: &[usize]) -> Self::Cost;
+
+    /// A lower bound on the cost of every sort that starts with `prefix`. The tighter
+    /// the bound, the more of the search is cut off.
+    fn lower_bound(&mut self, prefix: &[usize]) -> Self::Cost;
+}
+
+/// Finds a topological sort of minimum cost, or `None` if the graph has a cycle.
+///
+/// Searches prefixes depth-first, extending each with the nodes whose predecessors
+/// are all placed, most promising first, and skipping any prefix whose lower bound is
+/// no better than the best sort found so far.
+pub fn best_toposort<G: Graph, O: Objective + ?Sized>(g: &G, obj: &mut O) -> Option<(Vec<usize>, O::Cost)> {
+    let n = g.size();
+
+    let mut in_deg = vec![0; n];
+    for (_, w) in g.edges() {
+        in_deg[w] += 1;
+    }
+    let mut placed = vec![false; n];
+
+    let mut best: Option<(Vec<usize>, O::Cost)> = None;
+    let mut l = Vec::with_capacity(n);
+    let mut stack = vec![];
+
+    if n == 0 {
+        let c = obj.cost(&l);
+        return Some((l, c));
+    }
+
+    stack.push((candidates(obj, &mut l, &in_deg, &placed), 0));
+
+    while let Some((cs, i)) = stack.last_mut() {
+        let next = cs.get(*i).filter(|(b, _)| best.as_ref().is_none_or(|(_, c)| b < c));
+        if let Some(&(_, x)) = next {
+            *i += 1;
+            placed[x] = true;
+            for w in g.successors(x) {
+                in_deg[w] -= 1;
+            }
+            l.push(x);
+            if l.len() < n {
+                stack.push((candidates(obj, &mut l, &in_deg, &placed), 0));
+                continue;
+            }
+            let c = obj.cost(&l);
+            if best.as_ref().is_none_or(|(_, b)| c < *b) {
+                best = Some((l.clone(), c));
+            }
+        } else {
+            stack.pop();
+            if stack.is_empty() {
+                break;
+            }
+        }
+        let x = l.pop().unwrap();
+        placed[x] = false;
+        for w in g.successors(x) {
+            in_deg[w] += 1;
+        }
+    }
+
+    best
+}
+
+// The nodes that can extend `l`, with their bounds, in increasing order of bound.
+fn candidates<O: Objective + ?Sized>(obj: &mut O, l: &mut Vec<usize>, in_deg: &[usize], placed: &[bool]) -> Vec<(O::Cost, usize)> {
+    let mut cs: Vec<_> = (0..in_deg.len()).filter(|&x| in_deg[x] == 0 && !placed[x]).map(|x| {
+        l.push(x);
+        let b = obj.lower_bound(l);
+        l.pop();
+        (b, x)
+    }).collect();
+    cs.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    cs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::graph::Graph;
+    use crate::nrpr::toposorts;
+    use crate::testing::dags;
+    use proptest::prelude::*;
+
+    // Total weighted completion time. Ignoring the edges, the rest of a prefix is best
+    // completed by decreasing weight, which gives a lower bound.
+    struct Weighted(Vec<u32>, usize);
+
+    impl Objective for Weighted {
+        type Cost = u32;
+
+        fn cost(&mut self, l: &[usize]) -> u32 {
+            l.iter().enumerate().map(|(i, &v)| (i as u32 + 1) * self.0[v]).sum()
+        }
+
+        fn lower_bound(&mut self, prefix: &[usize]) -> u32 {
+            self.1 += 1;
+            let mut rest: Vec<_> = (0..self.0.len()).filter(|v| !prefix.contains(v)).collect();
+            rest.sort_by_key(|&v| std::cmp::Reverse(self.0[v]));
+            let l: Vec<_> = prefix.iter().copied().chain(rest).collect();
+            self.cost(&l)
+        }
+    }
+
+    #[test]
+    fn test_prunes() {
+        let n = 8;
+        let g = SimpleGraph::new(n);
+        let mut obj = Weighted((1..=n as u32).collect(), 0);
+        let (l, c) = best_toposort(&g, &mut obj).unwrap();
+        assert_eq!(l, vec![7, 6, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(c, 120);
+        assert!(obj.1 < 1000);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(2);
+        g.add_edge(0, 1);
+        g.add_edge(1, 0);
+        assert!(best_toposort(&g, &mut Weighted(vec![1, 1], 0)).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_exhaustive(
+            (g, weights) in dags(1..7, 0..8).prop_flat_map(|g| {
+                let n = g.size();
+                (Just(g), prop::collection::vec(0..10u32, n))
+            })
+        ) {
+            let mut obj = Weighted(weights, 0);
+            let (l, c) = best_toposort(&g, &mut obj).unwrap();
+            let min = toposorts(g.clone()).map(|l| obj.cost(&l)).min().unwrap();
+            prop_assert_eq!(c, min);
+            prop_assert_eq!(obj.cost(&l), c);
+            prop_assert!(toposorts(g).any(|t| t == l));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "rayon")]
+
+use crate::graph::Graph;
+use crate::graph::simple::SimpleGraph;
+use crate::nrpr::toposort;
+use crate::prefix::{split, Subproblem};
+
+use rayon::prelude::*;
+
+/// Enumerates the topological sorts of a graph in parallel, in no particular order.
+///
+/// The sorts are split by their first few nodes into many more parts than there are
+/// threads in the current pool, each of which is enumerated with NRPR on what is left
+/// of the graph.
+///
+/// Panics if the graph has a cycle.
+pub fn par_toposorts<G: Graph>(g: &G) -> impl ParallelIterator<Item = Vec<usize>> {
+    toposort(g).unwrap_or_else(|e| panic!("{e}"));
+
+    // One shared copy, so that `G` need not be `Sync` and each part only copies the
+    // graph when a thread gets to it.
+    let mut graph = SimpleGraph::new(g.size());
+    for (v, w) in g.edges() {
+        graph.add_edge(v, w);
+    }
+
+    let parts = 8 * rayon::current_num_threads();
+    split(&graph, parts).into_par_iter().flat_map_iter(move |p| Subproblem::new(&graph, p).toposorts())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::count_toposorts;
+    use crate::testing::{check_toposorts, dags};
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_wide_antichain() {
+        // Only prefixes are kept until a thread takes a part.
+        let g = SimpleGraph::new(4000);
+        assert!(par_toposorts(&g).find_any(|l| l.len() == 4000).is_some());
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_nrpr(g in dags(0..8, 0..12)) {
+            check_toposorts(&g, par_toposorts(&g).collect::<Vec<_>>())?;
+        }
+
+        #[test]
+        fn test_split(g in dags(0..8, 0..12), parts in 1..40usize) {
+            let prefixes = split(&g, parts);
+            let total: usize = prefixes.iter().map(|p| Subproblem::new(&g, p.clone()).toposorts().count()).sum();
+            prop_assert_eq!(BigUint::from(total), count_toposorts(&g));
+            prop_assert!(prefixes.len() <= parts + g.size());
+        }
+    }
+}
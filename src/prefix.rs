@@ -0,0 +1,94 @@
+use crate::count::frontier;
+#[cfg(feature = "rayon")]
+use crate::count::preds;
+use crate::graph::Graph;
+use crate::graph::simple::SimpleGraph;
+use crate::nrpr::toposorts;
+
+use bit_vec::BitVec;
+
+#[cfg(feature = "rayon")]
+use std::collections::VecDeque;
+
+// The topological sorts of a graph that start with a fixed prefix and end with a
+// fixed suffix, as the sorts of the subgraph left after removing both.
+pub(crate) struct Subproblem {
+    pub(crate) prefix: Vec<usize>,
+    pub(crate) suffix: Vec<usize>,
+    pub(crate) graph: SimpleGraph,
+    // Maps the nodes of `graph` back to those of the original graph.
+    pub(crate) nodes: Vec<usize>,
+}
+
+impl Subproblem {
+    pub(crate) fn new<G: Graph>(g: &G, prefix: Vec<usize>) -> Self {
+        Self::with_suffix(g, prefix, vec![])
+    }
+
+    pub(crate) fn with_suffix<G: Graph>(g: &G, prefix: Vec<usize>, suffix: Vec<usize>) -> Self {
+        let n = g.size();
+
+        let mut removed = BitVec::from_elem(n, false);
+        for &v in prefix.iter().chain(&suffix) {
+            removed.set(v, true);
+        }
+
+        let mut index = vec![n; n];
+        let mut nodes = Vec::with_capacity(n - prefix.len() - suffix.len());
+        for v in (0..n).filter(|&v| !removed[v]) {
+            index[v] = nodes.len();
+            nodes.push(v);
+        }
+
+        let mut graph = SimpleGraph::new(nodes.len());
+        for (v, w) in g.edges() {
+            if !removed[v] && !removed[w] {
+                graph.add_edge(index[v], index[w]);
+            }
+        }
+
+        Subproblem { prefix, suffix, graph, nodes }
+    }
+
+    pub(crate) fn toposorts(self) -> impl Iterator<Item = Vec<usize>> {
+        let Subproblem { prefix, suffix, graph, nodes } = self;
+        toposorts(graph).map(move |l| {
+            prefix.iter().copied().chain(l.into_iter().map(|v| nodes[v])).chain(suffix.iter().copied()).collect()
+        })
+    }
+}
+
+// The prefixes one node longer than `p`.
+pub(crate) fn children(preds: &[Vec<usize>], p: &[usize]) -> Vec<Vec<usize>> {
+    let mut d = BitVec::from_elem(preds.len(), false);
+    for &v in p {
+        d.set(v, true);
+    }
+    frontier(preds, &d).map(|x| {
+        let mut p = p.to_vec();
+        p.push(x);
+        p
+    }).collect()
+}
+
+// Extends prefixes breadth-first, one at a time, until there are at least `parts` of
+// them or they cover whole sorts. Every topological sort starts with exactly one of them.
+#[cfg(feature = "rayon")]
+pub(crate) fn split<G: Graph>(g: &G, parts: usize) -> Vec<Vec<usize>> {
+    let preds = preds(g);
+    let n = preds.len();
+
+    let mut prefixes = VecDeque::from([vec![]]);
+
+    while prefixes.len() < parts {
+        match prefixes.front() {
+            Some(p) if p.len() < n => {
+                let p = prefixes.pop_front().unwrap();
+                prefixes.extend(children(&preds, &p));
+            },
+            _ => break,
+        }
+    }
+
+    prefixes.into()
+}
@@ -0,0 +1,129 @@
+use crate::count::Lattice;
+use crate::graph::Graph;
+
+use bit_vec::BitVec;
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+/// Ranks and unranks the topological sorts of a graph in lexicographic order.
+///
+/// Setup takes time and memory proportional to the number of downsets of the graph.
+/// Reuse a `Ranker` rather than calling [`rank`] or [`unrank`] repeatedly.
+pub struct Ranker {
+    lattice: Lattice,
+}
+
+impl Ranker {
+    pub fn new<G: Graph>(g: &G) -> Self {
+        Ranker { lattice: Lattice::new(g) }
+    }
+
+    pub fn count(&self) -> &BigUint {
+        self.lattice.count(&BitVec::from_elem(self.lattice.size(), false))
+    }
+
+    /// Returns `None` if `l` is not a topological sort of the graph.
+    pub fn rank(&self, l: &[usize]) -> Option<BigUint> {
+        let n = self.lattice.size();
+        if l.len() != n {
+            return None;
+        }
+
+        let mut d = BitVec::from_elem(n, false);
+        let mut r = BigUint::zero();
+
+        for &v in l {
+            let mut found = false;
+            for (x, c) in self.lattice.choices(&d) {
+                if x == v {
+                    found = true;
+                    break;
+                }
+                r += c;
+            }
+            if !found {
+                return None;
+            }
+            d.set(v, true);
+        }
+
+        Some(r)
+    }
+
+    /// Returns `None` if `index` is not less than the number of topological sorts.
+    pub fn unrank(&self, index: &BigUint) -> Option<Vec<usize>> {
+        let n = self.lattice.size();
+        if index >= self.count() {
+            return None;
+        }
+
+        let mut d = BitVec::from_elem(n, false);
+        let mut r = index.clone();
+        let mut l = Vec::with_capacity(n);
+
+        while l.len() < n {
+            let mut next = None;
+            for (x, c) in self.lattice.choices(&d) {
+                if r < *c {
+                    next = Some(x);
+                    break;
+                }
+                r -= c;
+            }
+            let x = next.unwrap();
+            d.set(x, true);
+            l.push(x);
+        }
+
+        Some(l)
+    }
+}
+
+/// The position of `l` among the topological sorts of `g` in lexicographic order.
+pub fn rank<G: Graph>(g: &G, l: &[usize]) -> Option<BigUint> {
+    Ranker::new(g).rank(l)
+}
+
+/// The topological sort of `g` at position `index` in lexicographic order.
+pub fn unrank<G: Graph>(g: &G, index: &BigUint) -> Option<Vec<usize>> {
+    Ranker::new(g).unrank(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::nrpr::toposorts;
+    use crate::testing::dags;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_invalid() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+
+        assert_eq!(rank(&g, &[1, 0, 2]), None);
+        assert_eq!(rank(&g, &[0, 1]), None);
+        assert_eq!(rank(&g, &[0, 0, 2]), None);
+        assert_eq!(rank(&g, &[0, 1, 3]), None);
+        assert_eq!(rank(&g, &[0, 1, 2]), Some(BigUint::zero()));
+        assert_eq!(unrank(&g, &BigUint::from(3u32)), None);
+        assert_eq!(unrank(&g, &BigUint::from(2u32)), Some(vec![2, 0, 1]));
+    }
+
+    proptest! {
+        #[test]
+        fn test_lexicographic(g in dags(1..7, 0..10)) {
+            let mut ts: Vec<_> = toposorts(g.clone()).collect();
+            ts.sort();
+
+            let ranker = Ranker::new(&g);
+            prop_assert_eq!(ranker.count(), &BigUint::from(ts.len()));
+            for (i, l) in ts.iter().enumerate() {
+                let i = BigUint::from(i);
+                prop_assert_eq!(ranker.unrank(&i), Some(l.clone()));
+                prop_assert_eq!(ranker.rank(l), Some(i));
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+#![cfg(feature = "rand")]
+
+use crate::count::Lattice;
+use crate::graph::Graph;
+use crate::Error;
+use crate::nrpr::{toposort, Adj, Adjacency};
+
+use bit_vec::BitVec;
+use num_bigint::RandBigInt;
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+
+/// Draws topological sorts exactly uniformly at random.
+///
+/// Setup takes time and memory proportional to the number of downsets of the graph,
+/// so this is only practical for graphs of moderate width. See [`MarkovChain`] for
+/// wider graphs.
+pub struct Sampler {
+    lattice: Lattice,
+}
+
+impl Sampler {
+    pub fn new<G: Graph>(g: &G) -> Result<Self, Error> {
+        toposort(g)?;
+        Ok(Sampler { lattice: Lattice::new(g) })
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        let n = self.lattice.size();
+        let mut d = BitVec::from_elem(n, false);
+        let mut l = Vec::with_capacity(n);
+
+        // Each next node is picked with probability proportional to the number of
+        // sorts that continue with it.
+        while l.len() < n {
+            let mut r = rng.gen_biguint_below(self.lattice.count(&d));
+            let mut next = None;
+            for (x, c) in self.lattice.choices(&d) {
+                if r < *c {
+                    next = Some(x);
+                    break;
+                }
+                r -= c;
+            }
+            let x = next.unwrap();
+            d.set(x, true);
+            l.push(x);
+        }
+
+        l
+    }
+}
+
+/// Draws topological sorts approximately uniformly at random, using the Bubley–Dyer
+/// chain on adjacent transpositions.
+///
+/// Each step takes O(log n) time to pick a position, plus O(log d) to check for an
+/// edge when the adjacency is stored sparsely, with d the out-degree of the node
+/// there. The chain mixes in O(n³ log n) steps, so samples taken fewer steps apart
+/// are correlated.
+pub struct MarkovChain {
+    adj: Adj,
+    l: Vec<usize>,
+    pos: Option<WeightedIndex<usize>>,
+}
+
+impl MarkovChain {
+    pub fn new<G: Graph>(g: &G) -> Result<Self, Error> {
+        let l = toposort(g)?;
+        let adj = Adj::new(g, Adjacency::Auto);
+        let n = l.len();
+        let pos = WeightedIndex::new((1..n).map(|i| i * (n - i))).ok();
+        Ok(MarkovChain { adj, l, pos })
+    }
+
+    pub fn current(&self) -> &[usize] {
+        &self.l
+    }
+
+    pub fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let Some(pos) = &self.pos else { return };
+        let i = pos.sample(rng);
+        // Adjacent nodes in a topological sort are comparable only through a direct edge.
+        if rng.gen() && !self.adj.has_edge(self.l[i], self.l[i + 1]) {
+            self.l.swap(i, i + 1);
+        }
+    }
+
+    /// Runs the chain for `steps` steps and returns the sort it ends on.
+    pub fn sample<R: Rng + ?Sized>(&mut self, rng: &mut R, steps: usize) -> &[usize] {
+        for _ in 0..steps {
+            self.step(rng);
+        }
+        &self.l
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::HashMap;
+
+    fn graph() -> SimpleGraph {
+        let mut g = SimpleGraph::new(5);
+        g.add_edge(0, 2);
+        g.add_edge(1, 2);
+        g.add_edge(2, 4);
+        g
+    }
+
+    fn is_toposort(g: &SimpleGraph, l: &[usize]) -> bool {
+        let mut pos = vec![0; l.len()];
+        for (i, &v) in l.iter().enumerate() {
+            pos[v] = i;
+        }
+        g.edges().all(|(v, w)| pos[v] < pos[w])
+    }
+
+    // Every one of the 10 sorts should show up about 1/10 of the time.
+    fn assert_uniform(g: &SimpleGraph, mut sample: impl FnMut() -> Vec<usize>) {
+        let runs = 10000;
+        let mut seen = HashMap::new();
+        for _ in 0..runs {
+            let l = sample();
+            assert!(is_toposort(g, &l));
+            *seen.entry(l).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), 10);
+        for &c in seen.values() {
+            assert!((850..1150).contains(&c), "{seen:?}");
+        }
+    }
+
+    #[test]
+    fn test_exact() {
+        let g = graph();
+        let mut rng = StdRng::seed_from_u64(1);
+        let sampler = Sampler::new(&g).unwrap();
+        assert_uniform(&g, || sampler.sample(&mut rng));
+    }
+
+    #[test]
+    fn test_markov_chain() {
+        let g = graph();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut chain = MarkovChain::new(&g).unwrap();
+        assert_uniform(&g, || chain.sample(&mut rng, 200).to_vec());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(2);
+        g.add_edge(0, 1);
+        g.add_edge(1, 0);
+        assert!(Sampler::new(&g).is_err());
+        assert!(MarkovChain::new(&g).is_err());
+    }
+
+    #[test]
+    fn test_trivial() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let g = SimpleGraph::new(1);
+        assert_eq!(Sampler::new(&g).unwrap().sample(&mut rng), vec![0]);
+        assert_eq!(MarkovChain::new(&g).unwrap().sample(&mut rng, 10), &[0]);
+    }
+}
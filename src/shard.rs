@@ -0,0 +1,109 @@
+use crate::count::{preds, Lattice};
+use crate::graph::Graph;
+use crate::graph::simple::SimpleGraph;
+use crate::nrpr::toposort;
+use crate::prefix::{children, Subproblem};
+
+use bit_vec::BitVec;
+use num_bigint::BigUint;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Enumerates the `i`th of `k` disjoint shards of the topological sorts of a graph.
+///
+/// Every sort is in exactly one shard, so together they give the same sorts as
+/// [`toposorts`](crate::nrpr::toposorts), though in a different order. The split only
+/// depends on the graph and `k`, and shards are balanced by counting the sorts that
+/// start with each of a set of prefixes, so this takes time proportional to the
+/// number of downsets of the graph before the first sort.
+///
+/// Panics if the graph has a cycle or `i >= k`.
+pub fn toposorts_shard<G: Graph>(g: &G, i: usize, k: usize) -> impl Iterator<Item = Vec<usize>> {
+    assert!(i < k, "shard {i} out of {k}");
+    toposort(g).unwrap_or_else(|e| panic!("{e}"));
+
+    let mut graph = SimpleGraph::new(g.size());
+    for (v, w) in g.edges() {
+        graph.add_edge(v, w);
+    }
+    let preds = preds(&graph);
+    let n = preds.len();
+
+    // The sorts starting with a prefix are the completions of the downset it covers,
+    // so one lattice counts every part. Only the prefix of each part is kept, and
+    // its count in the heap.
+    let lattice = Lattice::new(&graph);
+    let count = |p: &[usize]| {
+        let mut d = BitVec::from_elem(n, false);
+        for &v in p {
+            d.set(v, true);
+        }
+        lattice.count(&d).clone()
+    };
+    let mut parts = vec![Some(vec![])];
+    let mut heap = BinaryHeap::new();
+
+    let total = count(&[]);
+    heap.push((total.clone(), Reverse(0)));
+
+    // Keep splitting the largest part while it is much larger than a fair share.
+    let target = total / (4 * k);
+    while parts.len() < 64 * k {
+        let Some((c, Reverse(j))) = heap.peek() else { break };
+        let prefix = parts[*j].as_ref().unwrap();
+        if *c <= target || prefix.len() == n {
+            break;
+        }
+        let ps = children(&preds, prefix);
+        let j = *j;
+        heap.pop();
+        parts[j] = None;
+        for p in ps {
+            heap.push((count(&p), Reverse(parts.len())));
+            parts.push(Some(p));
+        }
+    }
+
+    // Assign the largest remaining parts first, each to the lightest shard.
+    let mut sorted = heap.into_sorted_vec();
+    sorted.reverse();
+
+    let mut loads = vec![BigUint::default(); k];
+    let mut shard = vec![];
+    for (c, Reverse(j)) in sorted {
+        let lightest = (0..k).min_by_key(|&s| &loads[s]).unwrap();
+        loads[lightest] += c;
+        if lightest == i {
+            shard.push(parts[j].take().unwrap());
+        }
+    }
+
+    shard.into_iter().flat_map(move |p| Subproblem::new(&graph, p).toposorts())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::testing::{check_toposorts, dags};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_balanced() {
+        let g = SimpleGraph::new(6);
+        for k in 1..8 {
+            for i in 0..k {
+                let c = toposorts_shard(&g, i, k).count();
+                assert!(c * k >= 720 * 3 / 4 && c * k <= 720 * 5 / 4, "shard {i} of {k} has {c}");
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_union(g in dags(0..7, 0..10), k in 1..6usize) {
+            check_toposorts(&g, (0..k).flat_map(|i| toposorts_shard(&g, i, k)))?;
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::count::{frontier, preds};
+use crate::graph::Graph;
+
+use bit_vec::BitVec;
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+use std::collections::HashMap;
+
+/// Whether `l`, a permutation of `0..l.len()`, has an odd number of inversions.
+pub fn is_odd(l: &[usize]) -> bool {
+    let mut seen = BitVec::from_elem(l.len(), false);
+    let mut cycles = 0;
+    for v in 0..l.len() {
+        if !seen[v] {
+            cycles += 1;
+            let mut w = v;
+            while !seen[w] {
+                seen.set(w, true);
+                w = l[w];
+            }
+        }
+    }
+    (l.len() - cycles) % 2 == 1
+}
+
+// The Gaussian binomial coefficient [n choose k] at q = -1.
+fn binomial_at_minus_one(n: usize, k: usize) -> BigInt {
+    if n.is_multiple_of(2) && !k.is_multiple_of(2) {
+        return BigInt::zero();
+    }
+    let (n, k) = (n / 2, k / 2);
+    let mut c = BigInt::one();
+    for i in 0..k {
+        c = c * (n - i) / (i + 1);
+    }
+    c
+}
+
+// The total size and sign imbalance of the shuffles of trees with the given sizes
+// and sign imbalances.
+fn shuffle<'a>(trees: impl Iterator<Item = (usize, &'a BigInt)>) -> (usize, BigInt) {
+    let (mut size, mut imbalance) = (0, BigInt::one());
+    for (s, i) in trees {
+        size += s;
+        imbalance *= i * binomial_at_minus_one(size, s);
+    }
+    (size, imbalance)
+}
+
+// The sign imbalance of a forest given by the children of each node, or `None` if
+// some node has more than one parent.
+//
+// Relative to a preorder labeling every subtree is a block of consecutive labels with
+// its root first, so the sorts of a forest are the shuffles of the sorts of its trees,
+// and the signs of the shuffles of blocks of sizes a and b add up to [a + b choose a]
+// at q = -1.
+fn forest(children: &[Vec<usize>]) -> Option<BigInt> {
+    let n = children.len();
+    let mut is_root = vec![true; n];
+    for &w in children.iter().flatten() {
+        if !is_root[w] {
+            return None;
+        }
+        is_root[w] = false;
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut stack: Vec<_> = (0..n).filter(|&v| is_root[v]).collect();
+    while let Some(v) = stack.pop() {
+        order.push(v);
+        stack.extend(&children[v]);
+    }
+    if order.len() < n {
+        // Whatever preorder missed lies on or below a cycle.
+        return Some(BigInt::zero());
+    }
+
+    let mut size = vec![0; n];
+    let mut imbalance = vec![BigInt::zero(); n];
+    for &v in order.iter().rev() {
+        let (s, i) = shuffle(children[v].iter().map(|&w| (size[w], &imbalance[w])));
+        size[v] = s + 1;
+        imbalance[v] = i;
+    }
+
+    let (_, i) = shuffle((0..n).filter(|&v| is_root[v]).map(|v| (size[v], &imbalance[v])));
+    Some(if is_odd(&order) { -i } else { i })
+}
+
+/// The number of even topological sorts of a graph minus the number of odd ones, as
+/// permutations of the nodes (see [`is_odd`]). Its absolute value does not depend on
+/// how the nodes are numbered.
+///
+/// Takes O(n) arithmetic operations on forests, directed either way. Otherwise takes
+/// time and memory proportional to the number of downsets, like
+/// [`count_toposorts`](crate::count::count_toposorts). A graph with a cycle has no sorts.
+pub fn sign_imbalance<G: Graph>(g: &G) -> BigInt {
+    let n = g.size();
+    let preds = preds(g);
+    let mut succ = vec![vec![]; n];
+    for (v, w) in g.edges() {
+        succ[v].push(w);
+    }
+
+    if let Some(i) = forest(&succ) {
+        return i;
+    }
+    // Reversing a sort of n nodes flips the order of all n(n - 1)/2 pairs.
+    if let Some(i) = forest(&preds) {
+        return if n * n.saturating_sub(1) / 2 % 2 == 1 { -i } else { i };
+    }
+
+    // Adding `x` after the downset `d` adds an inversion for every larger node in `d`.
+    let mut level = HashMap::from([(BitVec::from_elem(n, false), BigInt::one())]);
+    for _ in 0..n {
+        let mut next = HashMap::new();
+        for (d, c) in level {
+            for x in frontier(&preds, &d) {
+                let odd = (x + 1..n).filter(|&v| d[v]).count() % 2 == 1;
+                let mut d1 = d.clone();
+                d1.set(x, true);
+                let t = next.entry(d1).or_insert_with(BigInt::zero);
+                if odd {
+                    *t -= &c;
+                } else {
+                    *t += &c;
+                }
+            }
+        }
+        level = next;
+    }
+
+    level.into_values().next().unwrap_or_else(BigInt::zero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::simple::SimpleGraph;
+    use crate::nrpr::toposorts;
+    use crate::testing::dags;
+    use proptest::prelude::*;
+
+    fn brute_force<G: Graph>(g: G) -> BigInt {
+        toposorts(g).map(|l| if is_odd(&l) { -1 } else { 1 }).sum::<i64>().into()
+    }
+
+    // A forest on `parents.len()` nodes, where `parents[i]` is either `i` for a root or
+    // an earlier node, with nodes renamed by `labels` and edges optionally reversed.
+    fn forests() -> impl Strategy<Value = SimpleGraph> {
+        (0..9usize).prop_flat_map(|n| {
+            let parents = (0..n).map(|i| 0..=i).collect::<Vec<_>>();
+            let labels = Just((0..n).collect::<Vec<_>>()).prop_shuffle();
+            (parents, labels, any::<bool>())
+        }).prop_map(|(parents, labels, reverse)| {
+            let mut g = SimpleGraph::new(parents.len());
+            for (i, &p) in parents.iter().enumerate().filter(|&(i, &p)| p != i) {
+                let (v, w) = (labels[p], labels[i]);
+                if reverse { g.add_edge(w, v) } else { g.add_edge(v, w) }
+            }
+            g
+        })
+    }
+
+    #[test]
+    fn test_is_odd() {
+        assert!(!is_odd(&[]));
+        assert!(!is_odd(&[0, 1, 2]));
+        assert!(is_odd(&[1, 0, 2]));
+        assert!(!is_odd(&[1, 2, 0]));
+        assert!(is_odd(&[2, 1, 0]));
+        assert!(!is_odd(&[3, 2, 1, 0, 4, 5]));
+    }
+
+    #[test]
+    fn test_chain_and_antichain() {
+        let mut g = SimpleGraph::new(4);
+        assert_eq!(sign_imbalance(&g), BigInt::zero());
+        for v in 0..3 {
+            g.add_edge(3 - v, 2 - v);
+        }
+        assert_eq!(sign_imbalance(&g), BigInt::one());
+        assert_eq!(sign_imbalance(&SimpleGraph::new(0)), BigInt::one());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+        g.add_edge(1, 0);
+        assert_eq!(sign_imbalance(&g), BigInt::zero());
+        g.add_edge(2, 0);
+        assert_eq!(sign_imbalance(&g), BigInt::zero());
+    }
+
+    proptest! {
+        #[test]
+        fn test_sign_imbalance(g in dags(0..8, 0..14)) {
+            prop_assert_eq!(sign_imbalance(&g), brute_force(g));
+        }
+
+        #[test]
+        fn test_forests(g in forests()) {
+            prop_assert_eq!(sign_imbalance(&g), brute_force(g));
+        }
+    }
+}
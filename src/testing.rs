@@ -0,0 +1,83 @@
+#![cfg(any(test, feature = "proptest"))]
+
+use crate::count::count_toposorts;
+use crate::graph::Graph;
+use crate::graph::simple::SimpleGraph;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+use proptest::test_runner::TestCaseError;
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Generates acyclic graphs with a number of nodes in `nodes` and, as far as there is
+/// room for them, a number of distinct edges in `edges`. Nodes are numbered in random
+/// order. Shrinks towards fewer nodes and edges, and towards `0..n` being a sort.
+pub fn dags(nodes: Range<usize>, edges: Range<usize>) -> impl Strategy<Value = SimpleGraph> {
+    assert!(!edges.is_empty(), "empty range of edges");
+    nodes.prop_flat_map(move |n| {
+        let pairs: Vec<_> = (0..n).flat_map(|v| (v + 1..n).map(move |w| (v, w))).collect();
+        let max = pairs.len();
+        let size = edges.start.min(max)..=(edges.end - 1).min(max);
+        let perm = Just((0..n).collect::<Vec<_>>()).prop_shuffle();
+        (perm, subsequence(pairs, size)).prop_map(move |(perm, pairs)| {
+            let mut g = SimpleGraph::new(n);
+            for (v, w) in pairs {
+                g.add_edge(perm[v], perm[w]);
+            }
+            g
+        })
+    })
+}
+
+/// Checks that `sorts` are exactly the topological sorts of `g`, each once.
+pub fn check_toposorts<G: Graph>(g: &G, sorts: impl IntoIterator<Item = Vec<usize>>) -> Result<(), TestCaseError> {
+    let n = g.size();
+    let mut seen = HashSet::new();
+
+    for l in sorts {
+        let mut pos = vec![n; n];
+        for (i, &v) in l.iter().enumerate() {
+            prop_assert!(v < n && pos[v] == n, "{:?} is not a permutation", l);
+            pos[v] = i;
+        }
+        prop_assert_eq!(l.len(), n, "{:?} is not a permutation", l);
+        for (v, w) in g.edges() {
+            prop_assert!(pos[v] < pos[w], "{:?} has {} after {}", l, v, w);
+        }
+        prop_assert!(!seen.contains(&l), "{:?} is repeated", l);
+        seen.insert(l);
+    }
+
+    prop_assert_eq!(BigUint::from(seen.len()), count_toposorts(g), "some sorts are missing");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nrpr::toposorts;
+
+    proptest! {
+        #[test]
+        fn test_dags(g in dags(0..8, 0..12)) {
+            prop_assert!(g.size() < 8);
+            prop_assert!(g.edge_count() < 12);
+            check_toposorts(&g, toposorts(g.clone()))?;
+        }
+    }
+
+    #[test]
+    fn test_check_toposorts() {
+        let mut g = SimpleGraph::new(3);
+        g.add_edge(0, 1);
+
+        assert!(check_toposorts(&g, toposorts(g.clone())).is_ok());
+        assert!(check_toposorts(&g, vec![vec![0, 1, 2], vec![0, 2, 1]]).is_err());
+        assert!(check_toposorts(&g, toposorts(g.clone()).chain([vec![2, 0, 1]])).is_err());
+        assert!(check_toposorts(&g, toposorts(g.clone()).chain([vec![1, 0, 2]])).is_err());
+        assert!(check_toposorts(&g, toposorts(g.clone()).chain([vec![0, 1]])).is_err());
+    }
+}